@@ -1,29 +1,30 @@
-use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
 use rand::thread_rng;
 use rand::Rng;
 use serde::Deserialize;
-use std::future::Future;
-use twilight_http::request::prelude::RequestReactionType;
+use twilight_http::request::channel::reaction::RequestReactionType;
 use twilight_http::Client;
+use twilight_interaction::message_command;
 use twilight_interaction::slash_command;
+use twilight_interaction::ActionRowBuilder;
+use twilight_interaction::CallbackDataBuilder;
 use twilight_interaction::Choices;
 use twilight_interaction::ComponentResponse;
+use twilight_interaction::ComponentsBuilder;
 use twilight_interaction::Context;
 use twilight_interaction::Handler;
 use twilight_interaction::IntoCallbackData;
 use twilight_interaction::Mentionable;
-use twilight_mention::Mention;
-use twilight_model::application::callback::CallbackData;
 use twilight_model::application::component::button::ButtonStyle;
-use twilight_model::application::component::ActionRow;
-use twilight_model::application::component::Button;
-use twilight_model::application::component::Component;
 use twilight_model::application::interaction::application_command::InteractionChannel;
 use twilight_model::channel::Message;
 use twilight_model::guild::Role;
-use twilight_model::id::GuildId;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::Id;
 use twilight_model::user::User;
 
 #[slash_command(description("Frobs some bits", bits = "The bits to frob"))]
@@ -46,6 +47,7 @@ pub fn random(_: Context) -> String {
     role = "A role",
     mentionable = "Something mentionable"
 ))]
+#[allow(clippy::too_many_arguments)]
 pub fn all_the_args(
     _: Context,
     string: String,
@@ -67,15 +69,10 @@ mentionable: {}",
         string,
         int,
         bool,
-        user.mention(),
-        channel.id.mention(),
-        role.mention(),
-        // Sadly, it isn't possible to integrate `Mentionable` properly with `twilight-mention`.
-        // I guess I could add a `mention` method which just returns a string though.
-        match mentionable {
-            Mentionable::User(user) => user.mention().to_string(),
-            Mentionable::Role(role) => role.mention().to_string(),
-        }
+        format_args!("<@{}>", user.id),
+        format_args!("<#{}>", channel.id),
+        format_args!("<@&{}>", role.id),
+        mentionable.mention(),
     )
 }
 
@@ -150,35 +147,28 @@ pub fn default(_: Context, type_option: Type) -> String {
 }
 
 #[slash_command(description("Create a counter"))]
-pub fn counter(_: Context) -> CallbackData {
-    CallbackData {
-        content: Some("0".to_string()),
-        components: Some(vec![Component::ActionRow(ActionRow {
-            components: vec![Component::Button(Button {
-                custom_id: Some("inc_count".to_string()),
-                disabled: false,
-                label: Some("+1".to_string()),
-                style: ButtonStyle::Primary,
-
-                emoji: None,
-                url: None,
-            })],
-        })]),
-
-        allowed_mentions: None,
-        embeds: vec![],
-        flags: None,
-        tts: None,
-    }
+pub fn counter(_: Context) -> (String, ComponentsBuilder) {
+    (
+        "0".to_string(),
+        ComponentsBuilder::new().row(ActionRowBuilder::new().button(
+            "inc_count",
+            "+1",
+            ButtonStyle::Primary,
+        )),
+    )
 }
 
-fn echo(_: Context, message: Message) -> String {
+#[message_command]
+pub fn echo(_: Context, message: Message) -> String {
     message.content
 }
 
-async fn add_smiley(ctx: Context, message: Message) -> CallbackData {
-    let response = match ctx
-        .http
+#[message_command]
+pub async fn add_smiley(ctx: Context, message: Message) -> InteractionResponseData {
+    let http = ctx
+        .http()
+        .expect("the handler was built with an HTTP client");
+    let response = match http
         .create_reaction(
             message.channel_id,
             message.id,
@@ -191,48 +181,27 @@ async fn add_smiley(ctx: Context, message: Message) -> CallbackData {
         Err(e) => format!("Network error: {}", e),
     };
 
-    CallbackData {
-        content: Some(response),
-        flags: None,
-
-        allowed_mentions: None,
-        components: None,
-        embeds: vec![],
-        tts: None,
-    }
+    CallbackDataBuilder::new().content(response).build()
 }
 
-pub async fn build_handler(guild_id: GuildId, http: Client) -> Handler {
+pub async fn build_handler(
+    application_id: Id<ApplicationMarker>,
+    guild_id: Id<GuildMarker>,
+    http: impl Into<Arc<Client>>,
+) -> Handler {
     Handler::builder(http)
-        .guild_command(guild_id, "all-the-args", all_the_args::describe())
-        .guild_command(guild_id, "counter", counter::describe())
-        .guild_command(guild_id, "default", default::describe())
-        .guild_command(guild_id, "frob", frob::describe())
-        .guild_command(guild_id, "greet", greet::describe())
-        .guild_command(guild_id, "random", random::describe())
-        .guild_command(guild_id, "rust-version", rust_version::describe())
-        // TODO: is it possible to get away without the `as fn(_, _) -> _`?
-        .guild_command(guild_id, "Echo", echo as fn(_, _) -> _)
-        .guild_command(
-            guild_id,
-            "Add Smiley",
-            // This boilerplate is dumb; you shouldn't have to box the future in the first place,
-            // but I can't just implement `CommandResponse` for all futures because rustc is conservative
-            // and assumes that `CallbackData` could implement it in the future.
-            (|context, message| Box::pin(add_smiley(context, message)))
-                as fn(_, _) -> Pin<Box<dyn Future<Output = CallbackData> + Send>>,
-        )
-        .component_handler(|_, message, interaction| {
-            if interaction.custom_id == "inc_count" {
-                let mut count = message.content.parse().unwrap_or(0);
-                count += 1;
-                ComponentResponse::Update(count.to_string().into_callback_data())
-            } else {
-                ComponentResponse::Message(
-                    format!("Unknown message component {}", interaction.custom_id)
-                        .into_callback_data(),
-                )
-            }
+        .application_id(application_id)
+        // Registers every command declared with the command macros, rather than listing them out by hand.
+        .auto_register_guild(guild_id)
+        .on_component("inc_count", |_, message, _| {
+            let mut count = message.content.parse().unwrap_or(0);
+            count += 1;
+            ComponentResponse::Update(count.to_string().into_callback_data())
+        })
+        .component_handler(|_, _, interaction| {
+            ComponentResponse::Message(
+                format!("Unknown message component {}", interaction.custom_id).into_callback_data(),
+            )
         })
         .build()
         .await
@@ -4,7 +4,6 @@ use std::sync::Arc;
 use commands::build_handler;
 use futures::StreamExt;
 use twilight_gateway::Cluster;
-use twilight_gateway::Event;
 use twilight_gateway::EventTypeFlags;
 use twilight_gateway::Intents;
 use twilight_http::Client;
@@ -18,28 +17,24 @@ async fn main() {
 
     let token = env::var("TOKEN").expect("Missing discord bot token");
 
-    let http = Client::new(token.clone());
-    http.set_application_id(
-        env::var("APP_ID")
-            .expect("Missing application ID")
-            .parse::<u64>()
-            .unwrap()
-            .into(),
-    );
+    let http = Arc::new(Client::new(token.clone()));
+    let application_id = env::var("APP_ID")
+        .expect("Missing application ID")
+        .parse()
+        .unwrap();
 
     let guild_id = env::var("GUILD_ID")
         .expect("Missing guild ID")
-        .parse::<u64>()
-        .unwrap()
-        .into();
+        .parse()
+        .unwrap();
 
-    let handler = build_handler(guild_id, http.clone()).await;
+    let handler = build_handler(application_id, guild_id, Arc::clone(&http)).await;
 
     let handler = Arc::new(handler);
 
     let (cluster, mut events) = Cluster::builder(token, Intents::empty())
         .event_types(EventTypeFlags::INTERACTION_CREATE)
-        .http_client(http.clone())
+        .http_client(http)
         .build()
         .await
         .expect("failed to start cluster");
@@ -48,18 +43,13 @@ async fn main() {
         cluster.up().await;
     });
 
+    // Only interaction events are requested, but `handle_gateway_event` ignores any others anyway.
     while let Some((_, event)) = events.next().await {
-        match event {
-            Event::InteractionCreate(event) => {
-                let handler = Arc::clone(&handler);
-                tokio::spawn(async move {
-                    if let Err(err) = handler.handle_event(*event).await {
-                        log::error!("{}", err);
-                    }
-                });
+        let handler = Arc::clone(&handler);
+        tokio::spawn(async move {
+            if let Err(err) = handler.handle_gateway_event(event).await {
+                log::error!("{}", err);
             }
-            // Ignore any other events
-            _ => {}
-        }
+        });
     }
 }
@@ -20,14 +20,12 @@ async fn main() {
     let token = env::var("TOKEN").expect("Missing discord bot token");
     let application_id = env::var("APP_ID")
         .expect("Missing application ID")
-        .parse::<u64>()
-        .unwrap()
-        .into();
+        .parse()
+        .unwrap();
     let guild_id = env::var("GUILD_ID")
         .expect("Missing guild ID")
-        .parse::<u64>()
-        .unwrap()
-        .into();
+        .parse()
+        .unwrap();
 
     let hex = env::var("PUBLIC_KEY").expect("Missing discord public key");
     let bytes: [u8; PUBLIC_KEY_LENGTH] =
@@ -35,9 +33,8 @@ async fn main() {
     let public_key = PublicKey::from_bytes(&bytes).expect("Public key was invalid");
 
     let http = Client::new(token.clone());
-    http.set_application_id(application_id);
 
-    let handler = build_handler(guild_id, http).await;
+    let handler = build_handler(application_id, guild_id, http).await;
 
     let handler = Arc::new(handler);
 
@@ -56,8 +53,11 @@ async fn main() {
             let (res, fut) = handler.handle_request(req, &public_key)?;
 
             // Run the deferred future, if any.
+            // This is runtime-agnostic, so any executor would do here.
             if let Some(fut) = fut {
-                tokio::spawn(fut);
+                tokio::spawn(fut.report_errors(|id, err| {
+                    log::error!("failed to send deferred response to {}: {}", id, err)
+                }));
             }
 
             // Convert the response into a hyper `Body`.
@@ -6,18 +6,31 @@ use quote::quote;
 use quote::ToTokens;
 use syn::parse::Parse;
 use syn::parse_macro_input;
-use syn::spanned::Spanned;
+use syn::Attribute;
 use syn::AttributeArgs;
+use syn::Expr;
+use syn::ExprGroup;
+use syn::ExprLit;
+use syn::ExprParen;
+use syn::ExprUnary;
+use syn::Fields;
 use syn::FnArg;
+use syn::GenericArgument;
 use syn::Ident;
 use syn::ItemEnum;
 use syn::ItemFn;
 use syn::Lit;
 use syn::LitStr;
 use syn::Meta;
+use syn::MetaList;
+use syn::MetaNameValue;
 use syn::NestedMeta;
 use syn::Pat;
+use syn::PathArguments;
 use syn::ReturnType;
+use syn::Type;
+use syn::TypeArray;
+use syn::UnOp;
 use syn::Token;
 
 /// A thing representing the parameters for an attribute of the form #[foo = "bar"].
@@ -35,6 +48,141 @@ impl Parse for EqStr {
     }
 }
 
+/// The type of the values of a `Choices` enum, set with `#[choices(...)]`.
+#[derive(Clone, Copy, PartialEq)]
+enum ChoiceKind {
+    Integer,
+    String,
+    Number,
+}
+
+/// The maximum number of characters Discord allows in a command or option description.
+const MAX_DESCRIPTION_LEN: usize = 100;
+
+/// The maximum number of characters Discord allows in a command or option name.
+const MAX_NAME_LEN: usize = 32;
+
+/// The maximum number of options Discord allows a command to have.
+const MAX_OPTIONS: usize = 25;
+
+/// The maximum number of choices Discord allows an option to have.
+const MAX_CHOICES: usize = 25;
+
+/// The largest magnitude an integer choice can have, since Discord's integers are stored as doubles.
+const MAX_INTEGER: i128 = 1 << 53;
+
+/// Checks that a command or option description is one Discord will accept.
+fn validate_description(description: &LitStr) -> syn::Result<()> {
+    let value = description.value();
+    let len = value.chars().count();
+
+    if len == 0 {
+        Err(syn::Error::new_spanned(description, "Descriptions cannot be empty"))
+    } else if len > MAX_DESCRIPTION_LEN {
+        Err(syn::Error::new_spanned(
+            description,
+            format!(
+                "Description {:?} is {} characters long, but Discord only allows up to {}",
+                value, len, MAX_DESCRIPTION_LEN
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses the arguments of a list like `default(foo = "bar", baz = "qux")`,
+/// where each key is the name of one of the function's arguments.
+fn option_name_values(list: &MetaList) -> syn::Result<Vec<(Ident, Lit)>> {
+    let error_message = format!(
+        "Options to `{}` must be of the form `ident = \"value\"`",
+        list.path.to_token_stream()
+    );
+
+    list.nested
+        .iter()
+        .map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(name_value)) => match name_value.path.get_ident() {
+                Some(ident) => Ok((ident.clone(), name_value.lit.clone())),
+                None => Err(syn::Error::new_spanned(
+                    &name_value.path,
+                    "The option name must be an ident",
+                )),
+            },
+            _ => Err(syn::Error::new_spanned(meta, &error_message)),
+        })
+        .collect()
+}
+
+/// Checks that `name` is a valid option name, which means it must be between 1 and [`MAX_NAME_LEN`] characters long,
+/// and can only contain the characters Discord allows: lowercase letters, digits, dashes and underscores.
+///
+/// This is the same whether or not `kebab_case = false` is passed, since that only changes how names are generated.
+fn validate_option_name(name: &LitStr) -> syn::Result<()> {
+    let value = name.value();
+    let len = value.chars().count();
+    if len == 0 || len > MAX_NAME_LEN {
+        Err(syn::Error::new_spanned(
+            name,
+            format!(
+                "Option names must be between 1 and {} characters long",
+                MAX_NAME_LEN
+            ),
+        ))
+    } else if value
+        .chars()
+        .all(|char| matches!(char, 'a'..='z' | '0'..='9' | '-' | '_'))
+    {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            name,
+            "Argument names can only contain lowercase letters, digits, dashes and underscores",
+        ))
+    }
+}
+
+/// Gets the length of an array argument, which must be an integer literal no larger than the number of options Discord allows.
+fn array_len(array: &TypeArray) -> syn::Result<usize> {
+    let len = match &array.len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse::<usize>()?,
+        len => {
+            return Err(syn::Error::new_spanned(
+                len,
+                "The length of array arguments must be an integer literal",
+            ))
+        }
+    };
+
+    if len == 0 || len > MAX_OPTIONS {
+        Err(syn::Error::new_spanned(
+            &array.len,
+            format!(
+                "Array arguments must have between 1 and {} elements",
+                MAX_OPTIONS
+            ),
+        ))
+    } else {
+        Ok(len)
+    }
+}
+
+/// Parses the value of an argument like `ephemeral = true`.
+fn bool_arg(name_value: &MetaNameValue) -> syn::Result<bool> {
+    match &name_value.lit {
+        Lit::Bool(lit) => Ok(lit.value),
+        lit => Err(syn::Error::new_spanned(
+            lit,
+            format!(
+                "`{}` must be either `true` or `false`",
+                name_value.path.to_token_stream()
+            ),
+        )),
+    }
+}
+
 // rustdoc complains about `twilight_model` not existing since this crate doesn't actually link to it,
 // but this should only really be viewed in the docs for `twilight_interaction` anyway.
 #[allow(rustdoc::broken_intra_doc_links)]
@@ -47,14 +195,69 @@ impl Parse for EqStr {
 /// A `description` parameter needs to be passed to the macro,
 /// to provide the description which Discord will display.
 ///
+/// Arguments of type `Option<T>` become optional options.
+/// Alternatively, `default(arg = "expr")` can be passed to give an argument a default value,
+/// in which case the option is optional but the function still takes a plain `T`,
+/// which is set to `expr` when the option isn't given.
+///
+/// Whether an option is required can also be set explicitly with `required(arg = true)` or `required(arg = false)`,
+/// which only changes what Discord is told and not how the argument is parsed:
+/// - An `Option<T>` argument marked `required(arg = true)` must be filled in by the user,
+///   but still receives `None` if Discord somehow doesn't send it.
+/// - A plain `T` argument marked `required(arg = false)` can be left out by the user,
+///   in which case the command fails with a [`Missing`] error instead of being called;
+///   use `Option<T>` or `default` if the function should handle that itself.
+///
+/// Arguments with a `default` can't be marked as required, since the default would never be used.
+/// Nested `Option`s like `Option<Option<T>>` aren't allowed, since an option can only be left out one way.
+/// `Option<T>` arguments can still have defaults, which are plain `T`s that are wrapped in `Some` when the option is left out.
+///
+/// Passing `autocomplete(arg)` makes Discord ask for suggestions while the user is filling in `arg`,
+/// which are provided by the handler registered with [`HandlerBuilder::autocomplete`].
+/// Only string, integer and number options can use autocomplete, and not ones with fixed choices;
+/// describing the command panics otherwise.
+///
+/// Discord doesn't have options which take several values, but array arguments like `users: [Option<User>; 3]`
+/// are split up into numbered options named `users-1`, `users-2` and `users-3`,
+/// which are collected back into an array when the command is used.
+/// Their description, `rename` and `required` settings apply to every option in the array.
+///
+/// Discord requires all the required options to come before the optional ones,
+/// so the options are reordered to put them first if necessary.
+///
+/// The command is named after the function by default, with underscores replaced by dashes,
+/// but a different name can be set with `name = "..."`. Either way, it's available as `NAME` in the generated module.
+///
+/// Passing `kebab_case = false` keeps the underscores in the command's and options' names instead,
+/// so `fn my_command(user_id: ...)` registers a command called `my_command` with an option called `user_id`.
+/// Array elements are then numbered like `users_1`. Either way, renamed options can use any of the characters Discord allows
+/// in names: lowercase letters, digits, dashes and underscores, up to 32 characters.
+///
+/// Passing `ephemeral = true` makes the command's responses only visible to the user who used it,
+/// unless the returned `InteractionResponseData` sets its own `flags`. For `async` commands,
+/// this applies to the loading message, which decides whether the eventual response is ephemeral.
+///
+/// The first argument of the function can be a [`Context`] (or `&Context`),
+/// whose state type determines what state the command can be used with.
+/// It can also be left out for commands which don't need it, in which case the command can be used with any state,
+/// although it's only auto-registered with handlers without state.
+///
+/// Arguments of type [`Locale`] aren't options; they're filled in with the language of the user who used the command.
+///
 /// The function needs to return either a [`String`], in most cases,
-/// or a [`CallbackData`] to set more advanced options.
+/// or a [`InteractionResponseData`] to set more advanced options.
+///
+/// Passing `loading = "..."` to an `async` command shows that message while it runs,
+/// instead of Discord's usual "thinking" indicator.
+///
+/// Commands which just do something and don't return anything are acknowledged with an ephemeral "✓" message
+/// once they finish, which can be changed with `acknowledge = "..."`.
 ///
 /// ```no_run
-/// use twilight_interaction::{slash_command, Handler};
+/// use twilight_interaction::{slash_command, Context, Handler};
 ///
 /// #[slash_command(description("Prints 'Hello!'"))]
-/// fn greet() -> String {
+/// fn greet(_: Context) -> String {
 ///     "Hello!".to_string()
 /// }
 ///
@@ -73,7 +276,12 @@ impl Parse for EqStr {
 /// ```
 ///
 /// [`Handler`]: struct.Handler.html
-/// [`CallbackData`]: ::twilight_model::application::callback::CallbackData
+/// [`HandlerBuilder::autocomplete`]: struct.HandlerBuilder.html#method.autocomplete
+/// [`Context`]: struct.Context.html
+/// [`Locale`]: struct.Locale.html
+/// [`Choices`]: trait.Choices.html
+/// [`InteractionResponseData`]: ::twilight_model::http::interaction::InteractionResponseData
+/// [`Missing`]: enum.OptionErrorReason.html#variant.Missing
 #[proc_macro_attribute]
 pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as AttributeArgs);
@@ -82,6 +290,14 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
     let mut description = None;
     let mut opt_descriptions = HashMap::new();
     let mut renames = HashMap::new();
+    let mut defaults = HashMap::new();
+    let mut required = HashMap::new();
+    let mut autocomplete = Vec::new();
+    let mut ephemeral = false;
+    let mut acknowledge = None;
+    let mut loading = None;
+    let mut kebab_case = true;
+    let mut name = None;
 
     for arg in args {
         match &arg {
@@ -91,7 +307,7 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
                         for meta in &list.nested {
                             match meta {
                                 NestedMeta::Lit(lit) => match lit {
-                                    Lit::Str(str) => description = Some(str.value()),
+                                    Lit::Str(str) => description = Some(str.clone()),
                                     _ => {
                                         return syn::Error::new_spanned(
                                             lit,
@@ -107,7 +323,7 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
                                             opt_descriptions.insert(
                                                 ident.clone(),
                                                 match &name_value.lit {
-                                                    Lit::Str(str) => str.value(),
+                                                    Lit::Str(str) => str.clone(),
                                                     lit => {
                                                         return syn::Error::new_spanned(
                                                             lit,
@@ -175,12 +391,134 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
                                 .into()
                             }
                         }
+                    } else if list.path.is_ident("default") {
+                        let name_values = match option_name_values(list) {
+                            Ok(name_values) => name_values,
+                            Err(err) => return err.into_compile_error().into(),
+                        };
+                        for (ident, lit) in name_values {
+                            let expr = match lit {
+                                Lit::Str(lit) => match lit.parse::<Expr>() {
+                                    Ok(expr) => expr,
+                                    Err(err) => return err.into_compile_error().into(),
+                                },
+                                lit => {
+                                    return syn::Error::new_spanned(
+                                        lit,
+                                        "Default values must be string literals containing an expression",
+                                    )
+                                    .into_compile_error()
+                                    .into()
+                                }
+                            };
+                            defaults.insert(ident, expr);
+                        }
+                    } else if list.path.is_ident("required") {
+                        let name_values = match option_name_values(list) {
+                            Ok(name_values) => name_values,
+                            Err(err) => return err.into_compile_error().into(),
+                        };
+                        for (ident, lit) in name_values {
+                            match lit {
+                                Lit::Bool(lit) => {
+                                    required.insert(ident, lit);
+                                }
+                                lit => {
+                                    return syn::Error::new_spanned(
+                                        lit,
+                                        "Options to `required` must be either `true` or `false`",
+                                    )
+                                    .into_compile_error()
+                                    .into()
+                                }
+                            }
+                        }
+                    } else if list.path.is_ident("autocomplete") {
+                        for meta in &list.nested {
+                            match meta {
+                                NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => {
+                                    autocomplete.push(path.get_ident().unwrap().clone())
+                                }
+                                _ => {
+                                    return syn::Error::new_spanned(
+                                        meta,
+                                        "Options to `autocomplete` must be idents, like `autocomplete(query)`",
+                                    )
+                                    .into_compile_error()
+                                    .into()
+                                }
+                            }
+                        }
                     } else {
                         return syn::Error::new_spanned(list, "Unexpected argument")
                             .into_compile_error()
                             .into();
                     }
                 }
+                Meta::NameValue(name_value) if name_value.path.is_ident("ephemeral") => {
+                    match bool_arg(name_value) {
+                        Ok(value) => ephemeral = value,
+                        Err(err) => return err.into_compile_error().into(),
+                    }
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("kebab_case") => {
+                    match bool_arg(name_value) {
+                        Ok(value) => kebab_case = value,
+                        Err(err) => return err.into_compile_error().into(),
+                    }
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("acknowledge") => {
+                    match &name_value.lit {
+                        Lit::Str(lit) if lit.value().is_empty() => {
+                            return syn::Error::new_spanned(
+                                lit,
+                                "The acknowledgement can't be empty",
+                            )
+                            .into_compile_error()
+                            .into()
+                        }
+                        Lit::Str(lit) => acknowledge = Some(lit.clone()),
+                        lit => {
+                            return syn::Error::new_spanned(
+                                lit,
+                                "The acknowledgement must be a string literal",
+                            )
+                            .into_compile_error()
+                            .into()
+                        }
+                    }
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("loading") => {
+                    match &name_value.lit {
+                        Lit::Str(lit) if lit.value().is_empty() => {
+                            return syn::Error::new_spanned(
+                                lit,
+                                "The loading message can't be empty",
+                            )
+                            .into_compile_error()
+                            .into()
+                        }
+                        Lit::Str(lit) => loading = Some(lit.clone()),
+                        lit => {
+                            return syn::Error::new_spanned(
+                                lit,
+                                "The loading message must be a string literal",
+                            )
+                            .into_compile_error()
+                            .into()
+                        }
+                    }
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("name") => {
+                    match &name_value.lit {
+                        Lit::Str(lit) => name = Some(lit.clone()),
+                        lit => {
+                            return syn::Error::new_spanned(lit, "The name must be a string literal")
+                                .into_compile_error()
+                                .into()
+                        }
+                    }
+                }
                 _ => {
                     return syn::Error::new_spanned(meta, "Unexpected argument")
                         .into_compile_error()
@@ -201,9 +539,28 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
     let mut opt_description = Vec::new();
     // `opt_name`, but modified so that it definitely won't conflict with any of our internal variable names.
     let mut opt_ident = Vec::new();
+    // The type to actually parse the option as; this is `Option<T>` for options with defaults, unless `T` is already an `Option`.
+    let mut opt_parse_type = Vec::new();
+    // Code to substitute the default value, for options with defaults.
+    let mut opt_default = Vec::new();
+    // Code to override whether the option is required, for options passed to `required`.
+    let mut opt_required = Vec::new();
+    // Code to turn on autocomplete, for options passed to `autocomplete`.
+    let mut opt_autocomplete = Vec::new();
+    // The expressions passed to the function for each argument; these only differ from `opt_ident` for arrays.
+    let mut call_arg = Vec::new();
+    // The idents of arguments which are filled in from the interaction's locale, rather than being options.
+    let mut locale_ident = Vec::new();
 
-    // Skip the context argument at the start.
-    for arg in item.sig.inputs.iter().skip(1) {
+    // The type of the context argument, which tells us what type of state the command uses,
+    // and whether it's taken by reference. Commands don't have to take a context at all.
+    let context = match item.sig.inputs.first() {
+        Some(FnArg::Typed(arg)) => context_arg(&arg.ty),
+        _ => None,
+    };
+
+    // Skip the context argument at the start, if there is one.
+    for arg in item.sig.inputs.iter().skip(context.is_some() as usize) {
         match arg {
             FnArg::Receiver(_) => {
                 return syn::Error::new_spanned(
@@ -214,87 +571,298 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
                 .into()
             }
             FnArg::Typed(arg) => {
-                opt_type.push(&*arg.ty);
-
-                match &*arg.pat {
-                    Pat::Ident(ident) => {
-                        match opt_descriptions.remove(&ident.ident) {
-                            Some(description) => opt_description.push(description),
-                            None => {
-                                return syn::Error::new_spanned(
-                                    arg,
-                                    format!("Missing description for `{}`", ident.ident),
-                                )
-                                .into_compile_error()
-                                .into()
-                            }
-                        }
-
-                        let name = match renames.remove(&ident.ident) {
-                            Some(name) => name,
-                            None => {
-                                // Slash command argument names are kebab-case, whereas Rust argument names are snake_case.
-                                // So, replace the underscores with dashes to translate.
-                                LitStr::new(
-                                    &ident.ident.to_string().replace('_', "-"),
-                                    ident.span(),
-                                )
-                            }
-                        };
-
-                        // Validate the name
-                        for char in name.value().chars() {
-                            match char {
-                                // Lowercase letters and dashes are allowed.
-                                'a'..='z' | '-' => {},
-                                // Any other characters are invalid for a slash command argument name.
-                                _ => return syn::Error::new_spanned(name, "Argument names must be kebab-case (or snake_case, when written as an identifier)").into_compile_error().into(),
-                            }
-                        }
-                        opt_name.push(name);
-                        opt_ident.push(Ident::new(&(ident.ident.to_string() + "_"), ident.span()));
-                    }
+                let ident = match &*arg.pat {
+                    Pat::Ident(ident) => &ident.ident,
                     pat => {
                         return syn::Error::new_spanned(pat, "Only plain idents are supported.")
                             .into_compile_error()
                             .into()
                     }
+                };
+
+                if is_locale(&arg.ty) {
+                    let ident = Ident::new(&format!("{}_", ident), ident.span());
+                    call_arg.push(quote!(#ident));
+                    locale_ident.push(ident);
+                    continue;
+                }
+
+                // Arguments like `users: [Option<User>; 5]` are split up into several numbered options of the element type.
+                let (ty, len) = match &*arg.ty {
+                    Type::Array(array) => match array_len(array) {
+                        Ok(len) => (&*array.elem, Some(len)),
+                        Err(err) => return err.into_compile_error().into(),
+                    },
+                    ty => (ty, None),
+                };
+
+                let has_default = defaults.contains_key(ident);
+
+                // An option can only be left out one way, so the inner `Option` would always be `Some`.
+                // Aliases of `Option` can't be seen here, so those are caught when describing the option instead.
+                let inner = option_inner(ty);
+                if inner.and_then(option_inner).is_some() {
+                    return syn::Error::new_spanned(
+                        ty,
+                        "Nested `Option`s aren't allowed, since there's only one way to leave out an option",
+                    )
+                    .into_compile_error()
+                    .into();
+                }
+                let optional = inner.is_some();
+                let required = match required.remove(ident) {
+                    Some(lit) if lit.value && has_default => {
+                        return syn::Error::new_spanned(
+                            lit,
+                            format!("`{}` has a default, so it can't be required", ident),
+                        )
+                        .into_compile_error()
+                        .into()
+                    }
+                    Some(lit) => quote!(::twilight_interaction::set_required(&mut option, #lit);),
+                    None => quote!(),
+                };
+
+                let autocomplete = match autocomplete.iter().position(|other| other == ident) {
+                    Some(i) => {
+                        autocomplete.remove(i);
+                        quote!(::twilight_interaction::set_autocomplete(&mut option);)
+                    }
+                    None => quote!(),
+                };
+
+                let (parse_type, default) = match defaults.remove(ident) {
+                    Some(_) if len.is_some() => {
+                        return syn::Error::new_spanned(
+                            arg,
+                            "Array arguments can't have defaults; use an array of `Option`s instead",
+                        )
+                        .into_compile_error()
+                        .into()
+                    }
+                    // `Option`s are already optional, so the default just replaces `None`
+                    // rather than wrapping them in another `Option`.
+                    Some(default) if optional => (
+                        quote!(#ty),
+                        quote!(.or_else(|| -> #ty { ::std::option::Option::Some(#default) })),
+                    ),
+                    Some(default) => {
+                        // The option isn't required, since we can fall back to the default,
+                        // but the function still gets a plain `T`.
+                        (
+                            quote!(::std::option::Option<#ty>),
+                            quote!(.unwrap_or_else(|| -> #ty { #default })),
+                        )
+                    }
+                    None => (quote!(#ty), quote!()),
+                };
+
+                let description = match opt_descriptions.remove(ident) {
+                    Some(description) => {
+                        if let Err(err) = validate_description(&description) {
+                            return err.into_compile_error().into();
+                        }
+                        description
+                    }
+                    None => {
+                        return syn::Error::new_spanned(
+                            arg,
+                            format!("Missing description for `{}`", ident),
+                        )
+                        .into_compile_error()
+                        .into()
+                    }
+                };
+
+                let name = match renames.remove(ident) {
+                    Some(name) => name,
+                    None if kebab_case => {
+                        // Slash command argument names are kebab-case, whereas Rust argument names are snake_case.
+                        // So, replace the underscores with dashes to translate.
+                        LitStr::new(&ident.to_string().replace('_', "-"), ident.span())
+                    }
+                    None => LitStr::new(&ident.to_string(), ident.span()),
+                };
+
+                if let Err(err) = validate_option_name(&name) {
+                    return err.into_compile_error().into();
+                }
+
+                // Array elements are numbered using the same separator as the rest of the name.
+                let separator = if kebab_case { '-' } else { '_' };
+
+                // For arrays, `name-1`, `name-2` etc., and otherwise just `name` itself.
+                let names_idents = match len {
+                    Some(len) => (1..=len)
+                        .map(|i| {
+                            (
+                                LitStr::new(&format!("{}{}{}", name.value(), separator, i), name.span()),
+                                Ident::new(&format!("{}_{}_", ident, i), ident.span()),
+                            )
+                        })
+                        .collect(),
+                    None => vec![(name, Ident::new(&format!("{}_", ident), ident.span()))],
+                };
+
+                let idents = names_idents.iter().map(|(_, ident)| ident);
+                call_arg.push(match len {
+                    Some(_) => quote!([#(#idents),*]),
+                    None => quote!(#(#idents)*),
+                });
+
+                for (name, ident) in names_idents {
+                    // The numbers added to array elements' names can push them over the length limit.
+                    if let Err(err) = validate_option_name(&name) {
+                        return err.into_compile_error().into();
+                    }
+
+                    // Options are matched up by name, so two with the same name would silently get each other's values.
+                    if opt_name
+                        .iter()
+                        .any(|other: &LitStr| other.value() == name.value())
+                    {
+                        return syn::Error::new_spanned(
+                            &name,
+                            format!("There's more than one option named `{}`", name.value()),
+                        )
+                        .into_compile_error()
+                        .into();
+                    }
+
+                    opt_type.push(ty);
+                    opt_name.push(name);
+                    opt_description.push(description.clone());
+                    opt_ident.push(ident);
+                    opt_parse_type.push(parse_type.clone());
+                    opt_default.push(default.clone());
+                    opt_required.push(required.clone());
+                    opt_autocomplete.push(autocomplete.clone());
                 }
             }
         }
     }
 
+    if let Some(ident) = defaults
+        .keys()
+        .chain(required.keys())
+        .chain(&autocomplete)
+        .next()
+    {
+        return syn::Error::new_spanned(ident, format!("No option named `{}`", ident))
+            .into_compile_error()
+            .into();
+    }
+
+    if opt_type.len() > MAX_OPTIONS {
+        return syn::Error::new_spanned(
+            &item.sig.inputs,
+            format!(
+                "Slash commands can have at most {} options, but this one has {}",
+                MAX_OPTIONS,
+                opt_type.len()
+            ),
+        )
+        .into_compile_error()
+        .into();
+    }
+
     let description = if let Some(description) = description {
-        LitStr::new(&description, Span::call_site())
+        if let Err(err) = validate_description(&description) {
+            return err.into_compile_error().into();
+        }
+        description
     } else {
         return syn::Error::new(Span::call_site(), "Missing description")
             .into_compile_error()
             .into();
     };
 
-    let output = match item.sig.output {
-        ReturnType::Default => return syn::Error::new_spanned(item.sig, "Slash commands cannot return nothing.\nThey must either return a `String` or a `CallbackData`.").into_compile_error().into(),
-        ReturnType::Type(_, ref ty) => ty.as_ref(),
-    };
-
     let fn_name = &item.sig.ident;
 
+    // Like option names, command names are kebab-case, so translate the function's name if one isn't given.
+    let name = name.unwrap_or_else(|| {
+        if kebab_case {
+            LitStr::new(&fn_name.to_string().replace('_', "-"), fn_name.span())
+        } else {
+            LitStr::new(&fn_name.to_string(), fn_name.span())
+        }
+    });
+    let len = name.value().chars().count();
+    if len == 0 || len > MAX_NAME_LEN {
+        return syn::Error::new_spanned(
+            &name,
+            format!(
+                "Command names must be between 1 and {} characters long",
+                MAX_NAME_LEN
+            ),
+        )
+        .into_compile_error()
+        .into();
+    }
+    if name.value().chars().any(|char| char.is_uppercase() || char.is_whitespace()) {
+        return syn::Error::new_spanned(
+            &name,
+            "Slash command names must be lowercase and can't contain spaces",
+        )
+        .into_compile_error()
+        .into();
+    }
+
     let gen_fn_name = Ident::new(&format!("__{}_describe", fn_name), fn_name.span());
 
-    let convert_res = if item.sig.asyncness.is_some() {
-        quote! {
-            let fut = Box::pin(async move {
-                <#output as IntoCallbackData>::into_callback_data(res.await)
-            });
+    // Commands without a context can be used with any type of state, so `describe` is generic over it.
+    // Auto-registration has to pick one, though, so those are only auto-registered with handlers without state.
+    let (generics, state_type, context_call_arg, auto_register_args) = match context {
+        Some((ty, by_ref)) => (
+            quote!(),
+            quote!(<#ty as ::twilight_interaction::ContextState>::State),
+            if by_ref {
+                quote!(&context,)
+            } else {
+                quote!(context,)
+            },
+            quote!(),
+        ),
+        None => (quote!(<__State>), quote!(__State), quote!(), quote!(::<()>, stateless)),
+    };
 
-            Ok((InteractionResponse::DeferredChannelMessageWithSource(EMPTY_CALLBACK), Some(fut)))
+    let convert_res = match &item.sig.output {
+        ReturnType::Default => {
+            let content = match acknowledge {
+                Some(lit) => lit,
+                None => LitStr::new("\u{2713}", Span::call_site()),
+            };
+            acknowledge_response(&item, &content)
         }
+        ReturnType::Type(_, ty) => {
+            if let Some(lit) = acknowledge {
+                return syn::Error::new_spanned(
+                    lit,
+                    "`acknowledge` only applies to commands which don't return anything",
+                )
+                .into_compile_error()
+                .into();
+            }
+            convert_response(&item, ty)
+        }
+    };
+    let prelude = prelude();
+
+    let make_ephemeral = if ephemeral {
+        quote!(::twilight_interaction::ephemeral_by_default(res))
     } else {
-        quote! {
-            let res = <#output as IntoCallbackData>::into_callback_data(res);
+        quote!(res)
+    };
 
-            Ok((InteractionResponse::ChannelMessageWithSource(res), None))
+    // Only `async` commands are deferred, so the loading message wouldn't ever be shown for anything else.
+    let make_ephemeral = match loading {
+        Some(lit) if item.sig.asyncness.is_none() => {
+            return syn::Error::new_spanned(lit, "`loading` only applies to `async` commands")
+                .into_compile_error()
+                .into()
         }
+        Some(lit) => quote!(::twilight_interaction::with_loading_message(#make_ephemeral, #lit)),
+        None => make_ephemeral,
     };
 
     let mut tokens = item.to_token_stream();
@@ -302,63 +870,89 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
     tokens.extend(quote! {
         // This needs to be in the same scope as the original function so that all the paths to the argument types stay correct.
         #[doc(hidden)]
-        pub fn #gen_fn_name() -> ::twilight_interaction::CommandDecl {
-            use ::std::boxed::Box;
-            use ::std::convert::From;
-            use ::std::option::Option::*;
-            use ::std::primitive::str;
-            use ::std::result::Result::*;
-            use ::std::string::String;
-            use ::std::vec;
-
-            use ::twilight_model::application::callback::CallbackData;
-            use ::twilight_model::application::callback::InteractionResponse;
+        #[allow(unused_variables)]
+        pub fn #gen_fn_name #generics() -> ::twilight_interaction::CommandDecl<#state_type> {
+            #prelude
+            use ::twilight_interaction::OptionError;
+            use ::twilight_interaction::OptionErrorReason;
             use ::twilight_interaction::SlashCommandOption;
-            use ::twilight_interaction::IntoCallbackData;
-
-            /// An empty `CallbackData`, to use for the pointless field of `InteractionResponse::DeferredChannelMessageWithSource`.
-            const EMPTY_CALLBACK: CallbackData = CallbackData {
-                allowed_mentions: None,
-                components: None,
-                content: None,
-                embeds: vec![],
-                flags: None,
-                tts: None,
-            };
 
-            let options = vec![
+            let mut options = vec![
                 #(
-                    <#opt_type as SlashCommandOption>::describe(<String as From<&str>>::from(#opt_name), <String as From<&str>>::from(#opt_description)),
+                    {
+                        #[allow(unused_mut)]
+                        let mut option = <#opt_parse_type as SlashCommandOption>::describe(<String as From<&str>>::from(#opt_name), <String as From<&str>>::from(#opt_description));
+                        #opt_required
+                        #opt_autocomplete
+                        option
+                    },
                 )*
             ];
+            // Discord requires the required options to come first; they're still matched up by name when parsing.
+            ::twilight_interaction::sort_options(&mut options);
 
             ::twilight_interaction::CommandDecl::Slash {
-                description: #description,
+                description: ::std::borrow::Cow::Borrowed(#description),
                 options,
+                default_permission: true,
                 handler: Box::new(|context, options, resolved| {
+                    // Every problem with the options is collected, so that they can all be reported at once.
+                    let mut errors = Vec::new();
+
                     #(
                         let mut #opt_ident = None;
                     )*
 
-                    for option in options {
+                    for option in ::twilight_interaction::flatten_options(options) {
                         #(
-                            if option.name() == #opt_name {
-                                #opt_ident = Some(option);
+                            if option.name == #opt_name {
+                                if #opt_ident.is_some() {
+                                    errors.push(OptionError {
+                                        name: ::std::clone::Clone::clone(&option.name),
+                                        reason: OptionErrorReason::Duplicate,
+                                    });
+                                } else {
+                                    #opt_ident = Some(option);
+                                }
                             } else
                         )*
                         // If there are arguments, this will be an else block, otherwise it'll just be a regular block.
                         {
-                            return Err(<String as From<&str>>::from(option.name()));
+                            errors.push(OptionError {
+                                name: ::std::clone::Clone::clone(&option.name),
+                                reason: OptionErrorReason::Unknown,
+                            });
                         }
                     }
 
                     #(
-                        let #opt_ident = <#opt_type as SlashCommandOption>::from_option(#opt_ident, resolved.as_ref()).ok_or(<String as From<&str>>::from(#opt_name))?;
+                        let #opt_ident = match <#opt_parse_type as SlashCommandOption>::parse_option(#opt_ident, resolved.as_ref()) {
+                            Ok(value) => Some(value #opt_default),
+                            Err(reason) => {
+                                errors.push(OptionError { name: <String as From<&str>>::from(#opt_name), reason });
+                                None
+                            }
+                        };
                     )*
 
-                    let res = #fn_name(context, #(#opt_ident),*);
+                    // Everything parsed successfully if there weren't any errors, so this only fails if there were.
+                    let (#(#opt_ident,)*) = match (#(#opt_ident,)*) {
+                        (#(Some(#opt_ident),)*) if errors.is_empty() => (#(#opt_ident,)*),
+                        _ => return Err(::twilight_interaction::OptionErrors(errors)),
+                    };
 
-                    #convert_res
+                    #(
+                        let #locale_ident = ::twilight_interaction::Locale::from_context(&context);
+                    )*
+
+                    let res = #fn_name(#context_call_arg #(#call_arg),*);
+
+                    let res: (
+                        ::twilight_model::http::interaction::InteractionResponse,
+                        Option<::twilight_interaction::DeferredFuture>,
+                    ) = #convert_res;
+
+                    Ok(#make_ephemeral)
                 })
             }
         }
@@ -367,44 +961,541 @@ pub fn slash_command(args: TokenStream, item: TokenStream) -> TokenStream {
         #[doc(hidden)]
         pub mod #fn_name {
             pub use super::#gen_fn_name as describe;
+
+            /// The name of the command, as typed in Discord.
+            pub const NAME: &::std::primitive::str = #name;
         }
+
+        ::twilight_interaction::__auto_register!(#name, #gen_fn_name #auto_register_args);
     });
 
     tokens.into()
 }
 
-#[proc_macro_derive(Choices, attributes(name))]
+/// If `ty` is a `Context` or `&Context`, returns the `Context` type and whether it's a reference.
+fn context_arg(ty: &Type) -> Option<(&Type, bool)> {
+    let (ty, by_ref) = match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => (&*reference.elem, true),
+        ty => (ty, false),
+    };
+    match ty {
+        Type::Path(path)
+            if path.qself.is_none()
+                && matches!(path.path.segments.last(), Some(segment) if segment.ident == "Context") =>
+        {
+            Some((ty, by_ref))
+        }
+        _ => None,
+    }
+}
+
+/// Parses the value of a number choice from an attribute like `#[value = 1.5]`.
+///
+/// Attributes can only contain literals, so negative values have to be written as strings, like `#[value = "-1.5"]`.
+fn number_value(attr: &Attribute) -> syn::Result<f64> {
+    let lit = match attr.parse_meta()? {
+        Meta::NameValue(name_value) => name_value.lit,
+        meta => return Err(syn::Error::new_spanned(meta, "Expected `#[value = ...]`")),
+    };
+    let value = match &lit {
+        Lit::Float(lit) => lit.base10_parse()?,
+        Lit::Int(lit) => lit.base10_parse()?,
+        Lit::Str(str) => str
+            .value()
+            .parse()
+            .map_err(|_| syn::Error::new_spanned(&lit, "Expected a number"))?,
+        _ => return Err(syn::Error::new_spanned(&lit, "Expected a number")),
+    };
+    if f64::is_finite(value) {
+        Ok(value)
+    } else {
+        Err(syn::Error::new_spanned(lit, "Number choices must be finite"))
+    }
+}
+
+/// Gets the value of a discriminant if it's an integer literal, possibly negated.
+///
+/// Discriminants can be arbitrary constant expressions, which can't be evaluated here, so those give `None`.
+fn literal_discriminant(expr: &Expr) -> syn::Result<Option<i128>> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse().map(Some),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => Ok(literal_discriminant(expr)?.map(|value| -value)),
+        Expr::Paren(ExprParen { expr, .. }) | Expr::Group(ExprGroup { expr, .. }) => {
+            literal_discriminant(expr)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// If `ty` is `Option<T>`, returns `T`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `ty` is `Locale`, which is filled in from the interaction instead of being an option.
+fn is_locale(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none()
+            && matches!(
+                path.path.segments.last(),
+                Some(segment) if segment.ident == "Locale" && segment.arguments.is_empty()
+            ),
+        _ => false,
+    }
+}
+
+/// The imports and constants needed by the code generated by `convert_response`,
+/// as well as the generated option-parsing code.
+fn prelude() -> proc_macro2::TokenStream {
+    quote! {
+        use ::std::boxed::Box;
+        use ::std::convert::From;
+        use ::std::option::Option::*;
+        use ::std::primitive::str;
+        use ::std::result::Result::*;
+        use ::std::string::String;
+        use ::std::vec;
+
+        use ::twilight_interaction::WithAttachments;
+        use ::twilight_model::http::interaction::InteractionResponseData;
+
+        /// An empty `InteractionResponseData`, to fill in with the fields a response actually sets.
+        const EMPTY_CALLBACK: InteractionResponseData = InteractionResponseData {
+            allowed_mentions: None,
+            attachments: None,
+            choices: None,
+            components: None,
+            content: None,
+            custom_id: None,
+            embeds: None,
+            flags: None,
+            title: None,
+            tts: None,
+        };
+    }
+}
+
+/// Generates an expression converting `res`, the result of calling `item`, into an `(InteractionResponse, Option<DeferredFuture>)`.
+///
+/// If `item` is async, the response is deferred until `res` completes.
+fn convert_response(item: &ItemFn, output: &Type) -> proc_macro2::TokenStream {
+    if item.sig.asyncness.is_some() {
+        quote! {{
+            let fut = Box::pin(async move {
+                <WithAttachments as From<#output>>::from(res.await)
+            });
+
+            (
+                ::twilight_model::http::interaction::InteractionResponse {
+                    kind: ::twilight_model::http::interaction::InteractionResponseType::DeferredChannelMessageWithSource,
+                    data: None,
+                },
+                Some(fut),
+            )
+        }}
+    } else {
+        // Responses with attachments have to be deferred, which `into_interaction_response` takes care of.
+        quote! {
+            <WithAttachments as From<#output>>::from(res).into_interaction_response()
+        }
+    }
+}
+
+/// Generates an expression like `convert_response`'s for a command which doesn't return anything,
+/// which acknowledges it with an ephemeral message saying `content` once it's finished.
+fn acknowledge_response(item: &ItemFn, content: &LitStr) -> proc_macro2::TokenStream {
+    let ack = quote! {
+        InteractionResponseData {
+            content: Some(<String as From<&str>>::from(#content)),
+            flags: Some(::twilight_model::channel::message::MessageFlags::EPHEMERAL),
+            ..EMPTY_CALLBACK
+        }
+    };
+
+    if item.sig.asyncness.is_some() {
+        quote! {{
+            let fut = Box::pin(async move {
+                res.await;
+                <WithAttachments as From<InteractionResponseData>>::from(#ack)
+            });
+
+            (
+                ::twilight_model::http::interaction::InteractionResponse {
+                    kind: ::twilight_model::http::interaction::InteractionResponseType::DeferredChannelMessageWithSource,
+                    data: Some(InteractionResponseData {
+                        flags: Some(::twilight_model::channel::message::MessageFlags::EPHEMERAL),
+                        ..EMPTY_CALLBACK
+                    }),
+                },
+                Some(fut),
+            )
+        }}
+    } else {
+        quote! {{
+            let () = res;
+            (
+                ::twilight_model::http::interaction::InteractionResponse {
+                    kind: ::twilight_model::http::interaction::InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(#ack),
+                },
+                None,
+            )
+        }}
+    }
+}
+
+/// The two kinds of context menu commands.
+#[derive(Clone, Copy)]
+enum ContextMenuKind {
+    Message,
+    User,
+}
+
+impl ContextMenuKind {
+    /// The name of both the type of the command's target and the `CommandDecl` variant for the command.
+    fn target(self) -> &'static str {
+        match self {
+            ContextMenuKind::Message => "Message",
+            ContextMenuKind::User => "User",
+        }
+    }
+}
+
+/// Generates the `describe` function for a message or user command.
+fn context_menu_command(args: AttributeArgs, item: ItemFn, kind: ContextMenuKind) -> TokenStream {
+    let mut name = None;
+
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("name") => {
+                match name_value.lit {
+                    Lit::Str(lit) => name = Some(lit),
+                    lit => {
+                        return syn::Error::new_spanned(lit, "The name must be a string literal")
+                            .into_compile_error()
+                            .into()
+                    }
+                }
+            }
+            arg => {
+                return syn::Error::new_spanned(arg, "Unexpected argument")
+                    .into_compile_error()
+                    .into()
+            }
+        }
+    }
+
+    let fn_name = &item.sig.ident;
+
+    // Context menu command names are displayed as-is, so turn `add_smiley` into `Add Smiley`.
+    let name = name.unwrap_or_else(|| {
+        let name = fn_name
+            .to_string()
+            .split('_')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        LitStr::new(&name, fn_name.span())
+    });
+
+    let len = name.value().chars().count();
+    if len == 0 || len > MAX_NAME_LEN {
+        return syn::Error::new_spanned(
+            &name,
+            format!(
+                "Command names must be between 1 and {} characters long",
+                MAX_NAME_LEN
+            ),
+        )
+        .into_compile_error()
+        .into();
+    }
+
+    let mut inputs = item.sig.inputs.iter();
+    let context_type = match (inputs.next(), inputs.next(), inputs.next()) {
+        (Some(FnArg::Typed(context)), Some(FnArg::Typed(_)), None) => &*context.ty,
+        _ => {
+            return syn::Error::new_spanned(
+                &item.sig.inputs,
+                format!(
+                    "{} commands must take exactly two arguments: a `Context` and the targeted `{}`",
+                    kind.target(),
+                    kind.target()
+                ),
+            )
+            .into_compile_error()
+            .into()
+        }
+    };
+
+    let output = match item.sig.output {
+        ReturnType::Default => {
+            return syn::Error::new_spanned(
+                &item.sig,
+                format!(
+                    "{} commands cannot return nothing.\nThey must either return a `String` or a `InteractionResponseData`.",
+                    kind.target()
+                ),
+            )
+            .into_compile_error()
+            .into()
+        }
+        ReturnType::Type(_, ref ty) => ty.as_ref(),
+    };
+
+    let gen_fn_name = Ident::new(&format!("__{}_describe", fn_name), fn_name.span());
+    let variant = Ident::new(kind.target(), Span::call_site());
+    let prelude = prelude();
+    let convert_res = convert_response(&item, output);
+
+    let mut tokens = item.to_token_stream();
+
+    tokens.extend(quote! {
+        // This needs to be in the same scope as the original function so that all the paths to the argument types stay correct.
+        #[doc(hidden)]
+        pub fn #gen_fn_name() -> ::twilight_interaction::CommandDecl<<#context_type as ::twilight_interaction::ContextState>::State> {
+            #prelude
+
+            ::twilight_interaction::CommandDecl::#variant {
+                default_permission: true,
+                handler: Box::new(|context, target| {
+                    let res = #fn_name(context, target);
+
+                    #convert_res
+                }),
+            }
+        }
+
+        // Create a module with the same name as the function which reexports our generated function, so that it's reexported along with the original function.
+        #[doc(hidden)]
+        pub mod #fn_name {
+            pub use super::#gen_fn_name as describe;
+
+            /// The name of the command, as displayed in Discord.
+            pub const NAME: &::std::primitive::str = #name;
+        }
+
+        ::twilight_interaction::__auto_register!(#name, #gen_fn_name);
+    });
+
+    tokens.into()
+}
+
+/// Declares a function usable as a message command, which shows up when right-clicking a message.
+///
+/// The function must take a `Context` and the `Message` that was clicked on,
+/// and return the response in the same way as [`slash_command`](macro@slash_command).
+///
+/// The command's name defaults to the name of the function in title case (`add_smiley` becomes `Add Smiley`),
+/// and can be overridden with `#[message_command(name = "...")]`.
+/// It's available as `NAME` in the generated module, alongside `describe`.
+#[proc_macro_attribute]
+pub fn message_command(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+    context_menu_command(args, item, ContextMenuKind::Message)
+}
+
+/// Declares a function usable as a user command, which shows up when right-clicking a user.
+///
+/// This works the same as [`message_command`](macro@message_command),
+/// except that the function takes the `User` that was clicked on instead of a `Message`.
+#[proc_macro_attribute]
+pub fn user_command(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+    context_menu_command(args, item, ContextMenuKind::User)
+}
+
+#[proc_macro_derive(Choices, attributes(choices, name, value))]
 pub fn derive_choices(item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as ItemEnum);
     let name = item.ident;
 
+    if item.variants.len() > MAX_CHOICES {
+        return syn::Error::new_spanned(
+            name,
+            format!(
+                "Choices enums can have at most {} variants, but this one has {}",
+                MAX_CHOICES,
+                item.variants.len()
+            ),
+        )
+        .into_compile_error()
+        .into();
+    }
+
+    // Whether the choices are integers, strings or numbers, set with `#[choices(string)]` etc.
+    let mut kind = ChoiceKind::Integer;
+    for attr in item.attrs.iter().filter(|attr| attr.path.is_ident("choices")) {
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => {
+                for meta in &list.nested {
+                    match meta {
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("string") => {
+                            kind = ChoiceKind::String
+                        }
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("integer") => {
+                            kind = ChoiceKind::Integer
+                        }
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("number") => {
+                            kind = ChoiceKind::Number
+                        }
+                        meta => {
+                            return syn::Error::new_spanned(
+                                meta,
+                                "Expected `string`, `integer` or `number`",
+                            )
+                            .into_compile_error()
+                            .into()
+                        }
+                    }
+                }
+            }
+            Ok(meta) => {
+                return syn::Error::new_spanned(meta, "Expected `#[choices(string)]`")
+                    .into_compile_error()
+                    .into()
+            }
+            Err(err) => return err.into_compile_error().into(),
+        }
+    }
+
     let mut next_discriminant = quote!(0);
+    // The value of `next_discriminant`, if it's known.
+    let mut next_value = Some(0);
 
     let mut names = Vec::with_capacity(item.variants.len());
     let mut values = Vec::with_capacity(item.variants.len());
     let mut display_names = Vec::with_capacity(item.variants.len());
 
     for variant in item.variants {
+        // Values are converted back into variants without any data to fill in fields with.
+        if !matches!(variant.fields, Fields::Unit) {
+            return syn::Error::new_spanned(&variant.fields, "Choices enums must have unit variants")
+                .into_compile_error()
+                .into();
+        }
+
+        let value_attr = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("value"));
+        match (value_attr, kind) {
+            (Some(attr), ChoiceKind::Integer) => {
+                return syn::Error::new_spanned(
+                    attr,
+                    "`#[value = ...]` can only be used with `#[choices(string)]` or `#[choices(number)]`",
+                )
+                .into_compile_error()
+                .into()
+            }
+            (None, ChoiceKind::Number) => {
+                return syn::Error::new_spanned(
+                    &variant.ident,
+                    "Number choices need a value, like `#[value = 1.5]`",
+                )
+                .into_compile_error()
+                .into()
+            }
+            _ => {}
+        }
+
         let name_attr = variant
             .attrs
-            .into_iter()
+            .iter()
             .find(|attr| attr.path.is_ident("name"));
 
         let name = if let Some(attr) = name_attr {
-            let tokens = attr.tokens.into();
+            let tokens = attr.tokens.clone().into();
             let args = parse_macro_input!(tokens as EqStr);
             args.str
         } else {
             LitStr::new(&variant.ident.to_string(), variant.ident.span())
         };
-        let value = variant
-            .discriminant
-            // The highest enum discriminants can currently go is 64 bits,
-            // and we only really care about having a unique value for each variant,
-            // so just using an `as` cast here is fine.
-            // (Also, Discord's integers can only go to 2**53 anyway. TODO add a check for that somehow)
-            .map(|(_, value)| quote!(#value as ::std::primitive::i64))
-            .unwrap_or(next_discriminant.clone());
+        let value = match kind {
+            ChoiceKind::String => {
+                // String values default to the name shown to users.
+                let value = match value_attr {
+                    Some(attr) => {
+                        let tokens = attr.tokens.clone().into();
+                        parse_macro_input!(tokens as EqStr).str
+                    }
+                    None => name.clone(),
+                };
+                quote!(#value)
+            }
+            ChoiceKind::Number => {
+                // This is always `Some`, since number choices without a value were rejected above.
+                match number_value(value_attr.unwrap()) {
+                    Ok(value) => {
+                        let value = proc_macro2::Literal::f64_unsuffixed(value);
+                        quote!(#value)
+                    }
+                    Err(err) => return err.into_compile_error().into(),
+                }
+            }
+            ChoiceKind::Integer => {
+                let value = match &variant.discriminant {
+                    Some((_, expr)) => match literal_discriminant(expr) {
+                        Ok(value) => value,
+                        Err(err) => return err.into_compile_error().into(),
+                    },
+                    None => next_value,
+                };
+
+                // Discord's integers can only go to 2**53, so anything past that would be silently rounded.
+                if let Some(value) = value {
+                    if !(-MAX_INTEGER..=MAX_INTEGER).contains(&value) {
+                        let message = format!(
+                            "Integer choices must be between -2^53 and 2^53, but this one is {}",
+                            value
+                        );
+                        return match &variant.discriminant {
+                            Some((_, expr)) => syn::Error::new_spanned(expr, message),
+                            None => syn::Error::new_spanned(&variant.ident, message),
+                        }
+                        .into_compile_error()
+                        .into();
+                    }
+                }
+                next_value = value.map(|value| value + 1);
+
+                variant
+                    .discriminant
+                    // The highest enum discriminants can currently go is 64 bits,
+                    // and we only really care about having a unique value for each variant,
+                    // so just using an `as` cast here is fine.
+                    .map(|(_, value)| quote!(#value as ::std::primitive::i64))
+                    .unwrap_or(next_discriminant.clone())
+            }
+        };
 
         next_discriminant = quote!(::std::primitive::i64::wrapping_add(#value, 1));
 
@@ -413,24 +1504,64 @@ pub fn derive_choices(item: TokenStream) -> TokenStream {
         display_names.push(name);
     }
 
+    let value_type = match kind {
+        ChoiceKind::Integer => quote!(::std::primitive::i64),
+        ChoiceKind::String => quote!(&'static ::std::primitive::str),
+        ChoiceKind::Number => quote!(::std::primitive::f64),
+    };
+
+    // Integer values are matched against constants, since discriminants can be arbitrary expressions,
+    // whereas string values are always literals and can be matched against directly.
+    // Floats can't be used as patterns, so number values are compared one by one.
+    let from_value = match kind {
+        ChoiceKind::String => quote! {
+            match value {
+                #(
+                    #values => ::std::option::Option::Some(Self::#names),
+                )*
+                #[allow(unreachable_patterns)]
+                _ => ::std::option::Option::None,
+            }
+        },
+        ChoiceKind::Number => quote! {
+            #(
+                #[allow(clippy::float_cmp)]
+                if value == #values {
+                    return ::std::option::Option::Some(Self::#names);
+                }
+            )*
+            ::std::option::Option::None
+        },
+        ChoiceKind::Integer => quote! {
+            #![allow(non_upper_case_globals)]
+            #(
+                const #names: ::std::primitive::i64 = #values;
+            )*
+            match value {
+                #(
+                    #names => ::std::option::Option::Some(Self::#names),
+                )*
+                #[allow(unreachable_patterns)]
+                _ => ::std::option::Option::None,
+            }
+        },
+    };
+
     (quote! {
         impl ::twilight_interaction::Choices for #name {
-            const CHOICES: &'static [(&'static ::std::primitive::str, ::std::primitive::i64)] = &[
-                #((#display_names, #values),)*
-            ];
+            type Value = #value_type;
 
-            fn from_discriminant(discriminant: ::std::primitive::i64) -> ::std::option::Option<Self> {
-                #![allow(non_upper_case_globals)]
+            const CHOICES: &'static [::twilight_interaction::Choice<#value_type>] = &[
                 #(
-                    const #names: ::std::primitive::i64 = #values;
+                    ::twilight_interaction::Choice {
+                        name: #display_names,
+                        value: #values,
+                    },
                 )*
-                match discriminant {
-                    #(
-                        #names => ::std::option::Option::Some(Self::#names),
-                    )*
-                    #[allow(unreachable_patterns)]
-                    _ => ::std::option::Option::None,
-                }
+            ];
+
+            fn from_value(value: #value_type) -> ::std::option::Option<Self> {
+                #from_value
             }
         }
     })
@@ -0,0 +1,88 @@
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::http::attachment::Attachment;
+use twilight_model::http::interaction::InteractionResponse;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::http::interaction::InteractionResponseType;
+
+use crate::DeferredFuture;
+use crate::IntoCallbackData;
+use crate::EMPTY_CALLBACK;
+
+/// A message to respond with, along with files to attach to it.
+///
+/// Discord requires files to be uploaded separately from the rest of the message,
+/// which can only be done by editing a deferred response. So, returning this from a command
+/// which isn't `async` still defers the response, then immediately fills it in with the message and its files.
+///
+/// This is also the output of [`DeferredFuture`]s, since any deferred response can have files attached.
+///
+/// # Examples
+/// ```no_run
+/// use twilight_interaction::{slash_command, WithAttachments};
+/// use twilight_model::http::attachment::Attachment;
+///
+/// #[slash_command(description("Sends a text file"))]
+/// fn file() -> WithAttachments {
+///     WithAttachments::new(
+///         "Here's your file:",
+///         vec![Attachment::from_bytes(
+///             "hello.txt".to_string(),
+///             b"Hello, world!".to_vec(),
+///             0,
+///         )],
+///     )
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithAttachments {
+    pub data: InteractionResponseData,
+    pub attachments: Vec<Attachment>,
+}
+
+impl WithAttachments {
+    /// Creates a message with `attachments` attached to it.
+    pub fn new(data: impl IntoCallbackData, attachments: Vec<Attachment>) -> Self {
+        Self {
+            data: data.into_callback_data(),
+            attachments,
+        }
+    }
+
+    /// Converts this into the response to send to Discord, deferring it if there are files to upload.
+    #[doc(hidden)]
+    pub fn into_interaction_response(self) -> (InteractionResponse, Option<DeferredFuture>) {
+        if self.attachments.is_empty() {
+            return (
+                InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(self.data),
+                },
+                None,
+            );
+        }
+
+        // Whether the message is ephemeral has to be decided when deferring it, since it can't be changed by editing.
+        let loading = InteractionResponseData {
+            flags: self.data.flags.map(|flags| flags & MessageFlags::EPHEMERAL),
+            ..EMPTY_CALLBACK
+        };
+
+        (
+            InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data: Some(loading),
+            },
+            Some(Box::pin(async move { self })),
+        )
+    }
+}
+
+impl<T: IntoCallbackData> From<T> for WithAttachments {
+    fn from(data: T) -> Self {
+        Self {
+            data: data.into_callback_data(),
+            attachments: Vec::new(),
+        }
+    }
+}
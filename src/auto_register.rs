@@ -0,0 +1,74 @@
+#[cfg(feature = "auto-register")]
+use std::any::Any;
+
+/// A command collected by the `auto-register` feature, submitted by the command macros.
+#[cfg(feature = "auto-register")]
+#[doc(hidden)]
+pub struct AutoCommand {
+    pub(crate) name: &'static str,
+    pub(crate) module_path: &'static str,
+    /// Whether the command doesn't take a [`Context`](crate::Context), and so was declared for handlers of any state,
+    /// even though it's only submitted for handlers without state.
+    // This is only used to warn about skipping these commands, which needs `tracing`.
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    pub(crate) stateless: bool,
+    /// Returns the command's `CommandDecl<S>`, boxed up since `S` differs between commands.
+    pub(crate) describe: fn() -> Box<dyn Any>,
+}
+
+#[cfg(feature = "auto-register")]
+impl AutoCommand {
+    pub fn new(
+        name: &'static str,
+        module_path: &'static str,
+        stateless: bool,
+        describe: fn() -> Box<dyn Any>,
+    ) -> Self {
+        Self {
+            name,
+            module_path,
+            stateless,
+            describe,
+        }
+    }
+
+    /// Whether this command was declared in `module` or one of its submodules.
+    pub(crate) fn is_in(&self, module: &str) -> bool {
+        module.is_empty()
+            || self.module_path == module
+            || self
+                .module_path
+                .strip_prefix(module)
+                .is_some_and(|rest| rest.starts_with("::"))
+    }
+}
+
+#[cfg(feature = "auto-register")]
+inventory::collect!(AutoCommand);
+
+/// Submits a command to be collected by [`HandlerBuilder::auto_register_global`](crate::HandlerBuilder::auto_register_global) and co.
+///
+/// Commands which don't take a `Context` are passed with `stateless` at the end.
+#[cfg(feature = "auto-register")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __auto_register {
+    ($name:expr, $describe:path) => {
+        $crate::inventory::submit! {
+            $crate::AutoCommand::new($name, ::std::module_path!(), false, || ::std::boxed::Box::new($describe()))
+        }
+    };
+    ($name:expr, $describe:path, stateless) => {
+        $crate::inventory::submit! {
+            $crate::AutoCommand::new($name, ::std::module_path!(), true, || ::std::boxed::Box::new($describe()))
+        }
+    };
+}
+
+/// Without the `auto-register` feature, commands aren't collected anywhere.
+#[cfg(not(feature = "auto-register"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __auto_register {
+    ($($tt:tt)*) => {};
+}
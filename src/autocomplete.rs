@@ -0,0 +1,270 @@
+use twilight_model::application::command::CommandOptionChoice;
+use twilight_model::application::command::Number;
+use twilight_model::application::interaction::application_command::CommandDataOption;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::application::interaction::application_command_autocomplete::ApplicationCommandAutocompleteDataOption;
+use twilight_model::application::interaction::application_command_autocomplete::ApplicationCommandAutocompleteDataOptionType as OptionType;
+
+use crate::Context;
+use crate::SlashCommandOption;
+
+pub(crate) type AutocompleteHandlerFn<S> =
+    Box<dyn Fn(Context<S>, AutocompleteInput) -> Vec<CommandOptionChoice> + Send + Sync>;
+
+/// What the user has entered so far, passed to an autocomplete handler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutocompleteInput {
+    /// The name of the option the user is currently typing in.
+    pub focused: String,
+    /// What the user has typed into the focused option so far.
+    pub partial: String,
+    /// The type of the focused option.
+    pub focused_kind: OptionType,
+    /// The other options the user has already filled in.
+    pub options: Vec<CommandDataOption>,
+}
+
+impl AutocompleteInput {
+    /// Parses the options sent with an autocomplete interaction, splitting out the focused option.
+    ///
+    /// Returns `None` if none of the options are focused.
+    pub(crate) fn parse(options: Vec<ApplicationCommandAutocompleteDataOption>) -> Option<Self> {
+        let mut focused = None;
+        let mut other_options = Vec::new();
+        split_options(options, &mut focused, &mut other_options);
+
+        focused.map(|(focused, focused_kind, partial)| Self {
+            focused,
+            partial,
+            focused_kind,
+            options: other_options,
+        })
+    }
+
+    /// Parses what the user has typed into the focused option so far as a `T`.
+    ///
+    /// Returns `None` if it isn't a valid `T` (yet); for example, a number option which is still empty.
+    pub fn partial_as<T: SlashCommandOption>(&self) -> Option<T> {
+        let option = into_data_option(
+            self.focused.clone(),
+            self.focused_kind,
+            self.partial.clone(),
+        )?;
+        T::from_option(Some(option), None)
+    }
+
+    /// Parses the already filled in option `name` as a `T`.
+    ///
+    /// Returns `None` if the option hasn't been filled in or isn't a valid `T`.
+    /// Options which need resolved data, like users and roles, can't be parsed,
+    /// since Discord doesn't send resolved data with autocomplete interactions.
+    pub fn option<T: SlashCommandOption>(&self, name: &str) -> Option<T> {
+        let option = self
+            .options
+            .iter()
+            .find(|option| option.name == name)
+            .cloned()?;
+        T::from_option(Some(option), None)
+    }
+}
+
+/// Sorts `options` into the focused option and the ones which have already been filled in,
+/// looking inside subcommands and subcommand groups, whose options are nested inside them.
+fn split_options(
+    options: Vec<ApplicationCommandAutocompleteDataOption>,
+    focused: &mut Option<(String, OptionType, String)>,
+    other_options: &mut Vec<CommandDataOption>,
+) {
+    for option in options {
+        match option.kind {
+            OptionType::SubCommand | OptionType::SubCommandGroup => {
+                split_options(option.options, focused, other_options)
+            }
+            _ if option.focused => {
+                *focused = Some((option.name, option.kind, option.value.unwrap_or_default()));
+            }
+            _ => {
+                // Options which haven't been filled in yet don't have a value.
+                if let Some(value) = option.value {
+                    other_options.extend(into_data_option(option.name, option.kind, value));
+                }
+            }
+        }
+    }
+}
+
+/// Converts an option from an autocomplete interaction, whose value is always a string,
+/// into the same form as the options sent with regular commands.
+///
+/// Returns `None` if `value` isn't valid for the option's type, which can happen for the focused option.
+fn into_data_option(name: String, kind: OptionType, value: String) -> Option<CommandDataOption> {
+    let value = match kind {
+        OptionType::Integer => CommandOptionValue::Integer(value.parse().ok()?),
+        OptionType::Number => CommandOptionValue::Number(Number(value.parse().ok()?)),
+        OptionType::Boolean => CommandOptionValue::Boolean(value.parse().ok()?),
+        // IDs are sent as strings, like everything else in autocomplete interactions.
+        OptionType::User => CommandOptionValue::User(value.parse().ok()?),
+        OptionType::Channel => CommandOptionValue::Channel(value.parse().ok()?),
+        OptionType::Role => CommandOptionValue::Role(value.parse().ok()?),
+        OptionType::Mentionable => CommandOptionValue::Mentionable(value.parse().ok()?),
+        OptionType::Attachment => CommandOptionValue::Attachment(value.parse().ok()?),
+        OptionType::String => CommandOptionValue::String(value),
+        // Subcommands are split up before getting here, and don't have values anyway.
+        OptionType::SubCommand | OptionType::SubCommandGroup => return None,
+    };
+
+    Some(CommandDataOption {
+        focused: false,
+        name,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::application::interaction::application_command::CommandDataOption;
+    use twilight_model::application::interaction::application_command::CommandOptionValue;
+    use twilight_model::application::interaction::application_command_autocomplete::ApplicationCommandAutocompleteDataOption;
+    use twilight_model::application::interaction::application_command_autocomplete::ApplicationCommandAutocompleteDataOptionType as OptionType;
+    use twilight_model::id::Id;
+
+    use super::into_data_option;
+    use super::AutocompleteInput;
+
+    fn option(
+        name: &str,
+        kind: OptionType,
+        value: Option<&str>,
+        focused: bool,
+    ) -> ApplicationCommandAutocompleteDataOption {
+        ApplicationCommandAutocompleteDataOption {
+            focused,
+            kind,
+            name: name.to_string(),
+            options: vec![],
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn nested(
+        name: &str,
+        kind: OptionType,
+        options: Vec<ApplicationCommandAutocompleteDataOption>,
+    ) -> ApplicationCommandAutocompleteDataOption {
+        ApplicationCommandAutocompleteDataOption {
+            options,
+            ..option(name, kind, None, false)
+        }
+    }
+
+    /// A filled in `count` option and a focused `query` option, which is being typed in.
+    fn options() -> Vec<ApplicationCommandAutocompleteDataOption> {
+        vec![
+            option("count", OptionType::Integer, Some("3"), false),
+            option("query", OptionType::String, Some("ru"), true),
+        ]
+    }
+
+    fn expected() -> AutocompleteInput {
+        AutocompleteInput {
+            focused: "query".to_string(),
+            partial: "ru".to_string(),
+            focused_kind: OptionType::String,
+            options: vec![CommandDataOption {
+                focused: false,
+                name: "count".to_string(),
+                value: CommandOptionValue::Integer(3),
+            }],
+        }
+    }
+
+    #[test]
+    fn top_level() {
+        assert_eq!(AutocompleteInput::parse(options()), Some(expected()));
+    }
+
+    #[test]
+    fn subcommand() {
+        let options = vec![nested("search", OptionType::SubCommand, options())];
+        assert_eq!(AutocompleteInput::parse(options), Some(expected()));
+    }
+
+    #[test]
+    fn subcommand_group() {
+        let options = vec![nested(
+            "books",
+            OptionType::SubCommandGroup,
+            vec![nested("search", OptionType::SubCommand, options())],
+        )];
+        assert_eq!(AutocompleteInput::parse(options), Some(expected()));
+    }
+
+    #[test]
+    fn missing_focused() {
+        let options = vec![
+            option("count", OptionType::Integer, Some("3"), false),
+            option("query", OptionType::String, Some("ru"), false),
+        ];
+        assert_eq!(AutocompleteInput::parse(options), None);
+    }
+
+    #[test]
+    fn unfilled_options_skipped() {
+        let options = vec![
+            option("count", OptionType::Integer, None, false),
+            option("query", OptionType::String, None, true),
+        ];
+        let input = AutocompleteInput::parse(options).unwrap();
+        assert_eq!(input.partial, "");
+        assert!(input.options.is_empty());
+    }
+
+    #[test]
+    fn partial_as() {
+        let input =
+            AutocompleteInput::parse(vec![option("count", OptionType::Integer, Some("12"), true)])
+                .unwrap();
+        assert_eq!(input.partial_as::<i64>(), Some(12));
+        assert_eq!(input.partial_as::<f64>(), Some(12.0));
+        assert_eq!(input.partial_as::<String>(), None);
+        assert_eq!(input.partial_as::<bool>(), None);
+    }
+
+    #[test]
+    fn partial_as_invalid_value() {
+        let input = AutocompleteInput::parse(vec![option(
+            "count",
+            OptionType::Integer,
+            Some("12a"),
+            true,
+        )])
+        .unwrap();
+        assert_eq!(input.partial_as::<i64>(), None);
+    }
+
+    #[test]
+    fn option_type_mismatch() {
+        let input = AutocompleteInput::parse(options()).unwrap();
+        assert_eq!(input.option::<i64>("count"), Some(3));
+        assert_eq!(input.option::<String>("count"), None);
+        assert_eq!(input.option::<i64>("missing"), None);
+    }
+
+    #[test]
+    fn into_data_option_types() {
+        let value = |kind, value: &str| {
+            into_data_option("option".to_string(), kind, value.to_string())
+                .map(|option| option.value)
+        };
+        assert_eq!(
+            value(OptionType::Boolean, "true"),
+            Some(CommandOptionValue::Boolean(true))
+        );
+        assert_eq!(
+            value(OptionType::User, "123"),
+            Some(CommandOptionValue::User(Id::new(123)))
+        );
+        assert_eq!(value(OptionType::User, "not an ID"), None);
+        assert_eq!(value(OptionType::SubCommand, ""), None);
+    }
+}
@@ -0,0 +1,141 @@
+use twilight_model::application::component::Component;
+use twilight_model::channel::embed::Embed;
+use twilight_model::channel::message::AllowedMentions;
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::http::interaction::InteractionResponseData;
+
+use crate::ComponentsBuilder;
+use crate::IntoCallbackData;
+use crate::EMPTY_CALLBACK;
+
+/// A builder for `InteractionResponseData`, to avoid having to write out every field by hand.
+///
+/// This can put together a message's content, embeds, components and flags all at once,
+/// and can be returned directly from a command.
+///
+/// # Examples
+/// ```
+/// use twilight_interaction::CallbackDataBuilder;
+///
+/// let data = CallbackDataBuilder::new()
+///     .content("Only you can see this!")
+///     .ephemeral()
+///     .build();
+///
+/// assert_eq!(data.content.as_deref(), Some("Only you can see this!"));
+/// ```
+///
+/// Content and embeds can also be returned together as a tuple, or turned into a builder to add more:
+///
+/// ```
+/// use twilight_interaction::CallbackDataBuilder;
+/// use twilight_model::channel::embed::Embed;
+///
+/// let embeds: Vec<Embed> = Vec::new();
+/// let data = CallbackDataBuilder::from(("Here you go:".to_string(), embeds))
+///     .ephemeral()
+///     .build();
+///
+/// assert_eq!(data.content.as_deref(), Some("Here you go:"));
+/// ```
+#[derive(Clone, Debug)]
+#[must_use = "builders have no effect unless built"]
+pub struct CallbackDataBuilder(InteractionResponseData);
+
+impl CallbackDataBuilder {
+    /// Creates a builder for an empty `InteractionResponseData`.
+    pub const fn new() -> Self {
+        Self(EMPTY_CALLBACK)
+    }
+
+    /// Sets the content of the message.
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.0.content = Some(content.into());
+        self
+    }
+
+    /// Adds an embed to the message.
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.0.embeds.get_or_insert_with(Vec::new).push(embed);
+        self
+    }
+
+    /// Adds several embeds to the message.
+    pub fn embeds(mut self, embeds: impl IntoIterator<Item = Embed>) -> Self {
+        self.0.embeds.get_or_insert_with(Vec::new).extend(embeds);
+        self
+    }
+
+    /// Adds a component to the message.
+    pub fn component(mut self, component: Component) -> Self {
+        self.0
+            .components
+            .get_or_insert_with(Vec::new)
+            .push(component);
+        self
+    }
+
+    /// Adds all the components from a [`ComponentsBuilder`] to the message.
+    pub fn components(mut self, components: ComponentsBuilder) -> Self {
+        self.0
+            .components
+            .get_or_insert_with(Vec::new)
+            .extend(components.build());
+        self
+    }
+
+    /// Sets which mentions in the message are allowed to ping people.
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.0.allowed_mentions = Some(allowed_mentions);
+        self
+    }
+
+    /// Makes the message only visible to the user who triggered the interaction.
+    pub fn ephemeral(mut self) -> Self {
+        let flags = self.0.flags.unwrap_or_else(MessageFlags::empty);
+        self.0.flags = Some(flags | MessageFlags::EPHEMERAL);
+        self
+    }
+
+    /// Adds `flags` to the message's flags, on top of any which are already set (like by [`ephemeral`](Self::ephemeral)).
+    pub fn flags(mut self, flags: MessageFlags) -> Self {
+        let existing = self.0.flags.unwrap_or_else(MessageFlags::empty);
+        self.0.flags = Some(existing | flags);
+        self
+    }
+
+    /// Sets whether the message is sent as text-to-speech.
+    pub fn tts(mut self, tts: bool) -> Self {
+        self.0.tts = Some(tts);
+        self
+    }
+
+    /// Creates the `InteractionResponseData`.
+    pub fn build(self) -> InteractionResponseData {
+        self.0
+    }
+}
+
+impl Default for CallbackDataBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IntoCallbackData for CallbackDataBuilder {
+    fn into_callback_data(self) -> InteractionResponseData {
+        self.build()
+    }
+}
+
+impl From<(String, Vec<Embed>)> for CallbackDataBuilder {
+    fn from((content, embeds): (String, Vec<Embed>)) -> Self {
+        Self::new().content(content).embeds(embeds)
+    }
+}
+
+impl IntoCallbackData for (String, Vec<Embed>) {
+    fn into_callback_data(self) -> InteractionResponseData {
+        CallbackDataBuilder::from(self).build()
+    }
+}
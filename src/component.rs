@@ -0,0 +1,52 @@
+use std::ops::Deref;
+
+use twilight_model::application::interaction::message_component::MessageComponentInteractionData;
+use twilight_model::id::Id;
+
+/// The data sent with a message component interaction.
+///
+/// This dereferences to the underlying `MessageComponentInteractionData`,
+/// and adds some helpers for reading the values selected in select menus.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComponentData {
+    pub data: MessageComponentInteractionData,
+}
+
+impl ComponentData {
+    /// The values the user selected, if this interaction came from a select menu.
+    ///
+    /// This is empty for buttons.
+    pub fn values(&self) -> &[String] {
+        &self.data.values
+    }
+
+    /// The first value the user selected, for select menus which only allow selecting one value.
+    pub fn value(&self) -> Option<&str> {
+        self.data.values.first().map(String::as_str)
+    }
+
+    /// Parses the selected values as IDs, for select menus where each value is a user, role or channel ID.
+    ///
+    /// Returns `None` if any of the values aren't valid IDs.
+    pub fn selected_ids<T>(&self) -> Option<Vec<Id<T>>> {
+        self.data
+            .values
+            .iter()
+            .map(|value| value.parse().ok())
+            .collect()
+    }
+}
+
+impl Deref for ComponentData {
+    type Target = MessageComponentInteractionData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl From<MessageComponentInteractionData> for ComponentData {
+    fn from(data: MessageComponentInteractionData) -> Self {
+        Self { data }
+    }
+}
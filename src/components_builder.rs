@@ -0,0 +1,214 @@
+use twilight_model::application::component::button::ButtonStyle;
+use twilight_model::application::component::ActionRow;
+use twilight_model::application::component::Button;
+use twilight_model::application::component::Component;
+use twilight_model::application::component::SelectMenu;
+use twilight_model::channel::ReactionType;
+use twilight_model::http::interaction::InteractionResponseData;
+
+use crate::CallbackDataBuilder;
+use crate::IntoCallbackData;
+
+/// The maximum number of action rows Discord allows on a message.
+const MAX_ACTION_ROWS: usize = 5;
+
+/// The maximum number of buttons Discord allows in an action row.
+const MAX_BUTTONS: usize = 5;
+
+/// A builder for the components of a message.
+///
+/// This can be combined with some content to respond with, by returning `(content, components)` from a command.
+///
+/// # Examples
+/// ```
+/// use twilight_interaction::{ActionRowBuilder, ComponentsBuilder};
+/// use twilight_model::application::component::button::ButtonStyle;
+///
+/// let components = ComponentsBuilder::new()
+///     .row(
+///         ActionRowBuilder::new()
+///             .button("yes", "Yes", ButtonStyle::Success)
+///             .button("no", "No", ButtonStyle::Danger),
+///     )
+///     .build();
+///
+/// assert_eq!(components.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use = "builders have no effect unless built"]
+pub struct ComponentsBuilder {
+    rows: Vec<Component>,
+}
+
+impl ComponentsBuilder {
+    /// Creates a builder with no action rows.
+    pub const fn new() -> Self {
+        Self { rows: Vec::new() }
+    }
+
+    /// Adds an action row.
+    ///
+    /// # Panics
+    /// Panics if this would take the message over Discord's limit of 5 action rows.
+    pub fn row(mut self, row: ActionRowBuilder) -> Self {
+        assert!(
+            self.rows.len() < MAX_ACTION_ROWS,
+            "Messages can have at most {} action rows",
+            MAX_ACTION_ROWS
+        );
+        self.rows.push(row.build());
+        self
+    }
+
+    /// Creates the list of components.
+    pub fn build(self) -> Vec<Component> {
+        self.rows
+    }
+}
+
+/// A builder for an action row, which is a row of either up to 5 buttons or a single select menu.
+#[derive(Clone, Debug, Default)]
+#[must_use = "builders have no effect unless built"]
+pub struct ActionRowBuilder {
+    components: Vec<Component>,
+}
+
+impl ActionRowBuilder {
+    /// Creates an empty action row.
+    pub const fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    /// Adds a button, which sends an interaction with `custom_id` when clicked.
+    ///
+    /// # Panics
+    /// Panics if the row already contains a select menu, or this would take the row over Discord's limit of 5 buttons.
+    pub fn button(
+        self,
+        custom_id: impl Into<String>,
+        label: impl Into<String>,
+        style: ButtonStyle,
+    ) -> Self {
+        self.button_component(Button {
+            custom_id: Some(custom_id.into()),
+            disabled: false,
+            emoji: None,
+            label: Some(label.into()),
+            style,
+            url: None,
+        })
+    }
+
+    /// Adds a button showing `emoji` next to its label, which sends an interaction with `custom_id` when clicked.
+    ///
+    /// `emoji` can be either a unicode emoji or one of the guild's custom emojis.
+    ///
+    /// # Panics
+    /// Panics if the row already contains a select menu, or this would take the row over Discord's limit of 5 buttons.
+    pub fn emoji_button(
+        self,
+        custom_id: impl Into<String>,
+        emoji: ReactionType,
+        label: Option<String>,
+        style: ButtonStyle,
+    ) -> Self {
+        self.button_component(Button {
+            custom_id: Some(custom_id.into()),
+            disabled: false,
+            emoji: Some(emoji),
+            label,
+            style,
+            url: None,
+        })
+    }
+
+    /// Adds a link button, which opens `url` when clicked instead of sending an interaction.
+    ///
+    /// # Panics
+    /// Panics if the row already contains a select menu, or this would take the row over Discord's limit of 5 buttons.
+    pub fn link_button(self, url: impl Into<String>, label: impl Into<String>) -> Self {
+        self.button_component(Button {
+            custom_id: None,
+            disabled: false,
+            emoji: None,
+            label: Some(label.into()),
+            style: ButtonStyle::Link,
+            url: Some(url.into()),
+        })
+    }
+
+    /// Adds an already constructed button.
+    ///
+    /// # Panics
+    /// Panics if the row already contains a select menu, or this would take the row over Discord's limit of 5 buttons.
+    ///
+    /// Also panics if the button is malformed, which Discord would otherwise reject with an unhelpful error:
+    /// link buttons must have a `url` and no `custom_id`, and all other buttons must have a `custom_id` and no `url`.
+    /// Every button needs either a label or an emoji, too.
+    pub fn button_component(mut self, button: Button) -> Self {
+        if button.style == ButtonStyle::Link {
+            assert!(
+                button.url.is_some() && button.custom_id.is_none(),
+                "Link buttons must have a URL and no custom ID"
+            );
+        } else {
+            assert!(
+                button.custom_id.is_some() && button.url.is_none(),
+                "Non-link buttons must have a custom ID and no URL"
+            );
+        }
+        assert!(
+            button.label.is_some() || button.emoji.is_some(),
+            "Buttons must have a label, an emoji or both"
+        );
+        assert!(
+            !self.has_select_menu(),
+            "Action rows containing a select menu can't contain anything else"
+        );
+        assert!(
+            self.components.len() < MAX_BUTTONS,
+            "Action rows can have at most {} buttons",
+            MAX_BUTTONS
+        );
+        self.components.push(Component::Button(button));
+        self
+    }
+
+    /// Adds a select menu, which has to be the only thing in its row.
+    ///
+    /// # Panics
+    /// Panics if the row already contains anything else.
+    pub fn select_menu(mut self, select_menu: SelectMenu) -> Self {
+        assert!(
+            self.components.is_empty(),
+            "Select menus must be the only thing in their action row"
+        );
+        self.components.push(Component::SelectMenu(select_menu));
+        self
+    }
+
+    fn has_select_menu(&self) -> bool {
+        self.components
+            .iter()
+            .any(|component| matches!(component, Component::SelectMenu(_)))
+    }
+
+    /// Creates the action row.
+    pub fn build(self) -> Component {
+        Component::ActionRow(ActionRow {
+            components: self.components,
+        })
+    }
+}
+
+impl IntoCallbackData for (String, ComponentsBuilder) {
+    fn into_callback_data(self) -> InteractionResponseData {
+        let (content, components) = self;
+        CallbackDataBuilder::new()
+            .content(content)
+            .components(components)
+            .build()
+    }
+}
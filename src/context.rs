@@ -1,6 +1,357 @@
+use std::any::Any;
+use std::cmp::Reverse;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::ops::Deref;
+use std::sync::Arc;
+
 use twilight_http::Client;
+use twilight_model::guild::Permissions;
+use twilight_model::guild::Role;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::marker::RoleMarker;
+use twilight_model::id::marker::UserMarker;
+use twilight_model::id::Id;
+
+use crate::Error;
+use crate::Extensions;
+use crate::ResponseEditor;
 
+/// Information passed to command handlers.
+///
+/// `S` is the type of the state shared between commands, set with [`Handler::builder_with_state`](crate::Handler::builder_with_state).
 #[derive(Debug, Clone)]
-pub struct Context {
-    pub http: Client,
+pub struct Context<S = ()> {
+    pub(crate) http: Option<Arc<Client>>,
+    pub(crate) application_id: Option<Id<ApplicationMarker>>,
+    pub(crate) state: S,
+    pub(crate) guild_id: Option<Id<GuildMarker>>,
+    pub(crate) user_id: Option<Id<UserMarker>>,
+    pub(crate) token: String,
+    pub(crate) locale: String,
+    pub(crate) extensions: Extensions,
+}
+
+/// Lets the `slash_command` macro find out what type of state a command's `Context` has.
+#[doc(hidden)]
+pub trait ContextState {
+    type State;
+}
+
+impl<S> ContextState for Context<S> {
+    type State = S;
+}
+
+/// The language the user who triggered an interaction has Discord set to, such as `en-US` or `de`.
+///
+/// When a slash command takes an argument of this type, it's filled in from the interaction
+/// instead of being registered as an option:
+///
+/// ```
+/// use twilight_interaction::slash_command;
+/// use twilight_interaction::Locale;
+///
+/// #[slash_command(description("Says hello in your language"))]
+/// fn hello(locale: Locale) -> String {
+///     match locale.as_str() {
+///         "de" => "Hallo!".to_string(),
+///         "fr" => "Bonjour !".to_string(),
+///         _ => "Hello!".to_string(),
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Locale(pub String);
+
+impl Locale {
+    /// Gets the locale of the interaction `context` is for.
+    #[doc(hidden)]
+    pub fn from_context<S>(context: &Context<S>) -> Self {
+        Locale(context.locale.clone())
+    }
+
+    /// The locale's code, like `en-US`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Locale {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A role, along with whether the bot is able to assign it to members.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AssignableRole {
+    pub role: Role,
+    /// Whether the bot can give this role to (or take it from) members.
+    ///
+    /// This is only the case if the role isn't @everyone or managed by an integration, and the bot either owns the guild,
+    /// or has the `MANAGE_ROLES` permission and a higher role than this one.
+    pub assignable: bool,
+}
+
+impl<S> Context<S> {
+    /// The state shared between all of the handler's commands,
+    /// such as a database pool or the bot's configuration.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// The HTTP client the handler was built with,
+    /// or `None` if it was built with [`Handler::builder_without_client`](crate::Handler::builder_without_client).
+    pub fn http(&self) -> Option<&Client> {
+        self.http.as_deref()
+    }
+
+    /// The ID of the guild the interaction happened in, or `None` if it happened in a DM.
+    pub fn guild_id(&self) -> Option<Id<GuildMarker>> {
+        self.guild_id
+    }
+
+    /// The ID of the user who triggered the interaction.
+    ///
+    /// This is only `None` when there isn't an interaction, like in [`DynamicChoices::choices`](crate::DynamicChoices::choices).
+    pub fn user_id(&self) -> Option<Id<UserMarker>> {
+        self.user_id
+    }
+
+    /// The language the user who triggered the interaction has Discord set to, such as `en-US` or `de`.
+    ///
+    /// Slash commands can also take a [`Locale`] argument to get this.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The value of type `T` attached to this interaction with [`Handler::handle_with`](crate::Handler::handle_with)
+    /// or [`Handler::handle_event_with`](crate::Handler::handle_event_with), if there is one.
+    pub fn extension<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.extensions.get()
+    }
+
+    /// All the extra data attached to this interaction.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// The token of the interaction being handled.
+    ///
+    /// This can be used with [`http`](Self::http) to send follow-up messages or edit the original response,
+    /// including from message component handlers, whose token is for the component interaction rather than the original command.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Creates a handle for editing or deleting the response to this interaction, or sending follow-ups,
+    /// which can outlive the command's handler. This can also show progress while the response is deferred.
+    pub fn response_editor(&self) -> ResponseEditor {
+        ResponseEditor::new(self.http.clone(), self.application_id, self.token.clone())
+    }
+
+    /// Checks whether the bot is able to assign `role` in the guild `guild_id`.
+    ///
+    /// This needs to fetch the bot's user, its member and the guild (for its owner and roles) from Discord,
+    /// which is three uncached HTTP requests every time it's called, so it's done here rather than while parsing the option.
+    pub async fn assignable_role(
+        &self,
+        guild_id: Id<GuildMarker>,
+        role: Role,
+    ) -> Result<AssignableRole, Error> {
+        let http = self.http().ok_or(Error::MissingClient)?;
+        let current_user = http.current_user().exec().await?.model().await?;
+        let member = http
+            .guild_member(guild_id, current_user.id)
+            .exec()
+            .await?
+            .model()
+            .await?;
+        let guild = http.guild(guild_id).exec().await?.model().await?;
+
+        Ok(AssignableRole {
+            assignable: is_assignable(
+                &role,
+                guild_id,
+                &guild.roles,
+                &member.roles,
+                guild.owner_id == current_user.id,
+            ),
+            role,
+        })
+    }
+}
+
+/// Whether a member with `member_roles` can assign `role` in the guild `guild_id`, whose roles are `roles`.
+fn is_assignable(
+    role: &Role,
+    guild_id: Id<GuildMarker>,
+    roles: &[Role],
+    member_roles: &[Id<RoleMarker>],
+    is_owner: bool,
+) -> bool {
+    // Every member implicitly has the @everyone role, which has the same ID as the guild,
+    // and roles managed by integrations can only be assigned by the integration.
+    if role.id.cast() == guild_id || role.managed {
+        return false;
+    }
+
+    // The owner can assign any role, whatever roles they have themselves.
+    if is_owner {
+        return true;
+    }
+
+    // Discord orders roles with the same position by ID, with the older (lower) ID coming higher.
+    let rank = |role: &Role| (role.position, Reverse(role.id));
+
+    let mut permissions = Permissions::empty();
+    let mut highest = None;
+    for member_role in roles
+        .iter()
+        .filter(|other| other.id.cast() == guild_id || member_roles.contains(&other.id))
+    {
+        permissions |= member_role.permissions;
+        highest = highest.max(Some(rank(member_role)));
+    }
+
+    permissions.intersects(Permissions::MANAGE_ROLES | Permissions::ADMINISTRATOR)
+        && Some(rank(role)) < highest
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::guild::Permissions;
+    use twilight_model::guild::Role;
+    use twilight_model::id::Id;
+
+    use super::is_assignable;
+
+    fn role(id: u64, position: i64, permissions: Permissions) -> Role {
+        Role {
+            color: 0,
+            hoist: false,
+            icon: None,
+            id: Id::new(id),
+            managed: false,
+            mentionable: false,
+            name: format!("role {}", id),
+            permissions,
+            position,
+            tags: None,
+            unicode_emoji: None,
+        }
+    }
+
+    /// The roles of a guild with the ID 1: @everyone, a moderator role (2) which can manage roles,
+    /// a role tied in position with the moderator role (3) and a role below it (4).
+    fn roles() -> Vec<Role> {
+        vec![
+            role(1, 0, Permissions::empty()),
+            role(2, 1, Permissions::MANAGE_ROLES),
+            role(3, 1, Permissions::empty()),
+            role(4, 0, Permissions::empty()),
+        ]
+    }
+
+    #[test]
+    fn below_highest_role() {
+        let roles = roles();
+        assert!(is_assignable(
+            &roles[3],
+            Id::new(1),
+            &roles,
+            &[Id::new(2)],
+            false
+        ));
+    }
+
+    #[test]
+    fn without_manage_roles() {
+        let roles = roles();
+        assert!(!is_assignable(
+            &roles[3],
+            Id::new(1),
+            &roles,
+            &[Id::new(3)],
+            false
+        ));
+    }
+
+    #[test]
+    fn position_ties_broken_by_id() {
+        let mut roles = roles();
+        // Role 3 has a higher ID than role 2, so it comes below it despite having the same position.
+        assert!(is_assignable(
+            &roles[2],
+            Id::new(1),
+            &roles,
+            &[Id::new(2)],
+            false
+        ));
+
+        roles[1].id = Id::new(5);
+        assert!(!is_assignable(
+            &roles[2],
+            Id::new(1),
+            &roles,
+            &[Id::new(5)],
+            false
+        ));
+    }
+
+    #[test]
+    fn own_highest_role() {
+        let roles = roles();
+        assert!(!is_assignable(
+            &roles[1],
+            Id::new(1),
+            &roles,
+            &[Id::new(2)],
+            false
+        ));
+    }
+
+    #[test]
+    fn everyone_role() {
+        let roles = roles();
+        assert!(!is_assignable(
+            &roles[0],
+            Id::new(1),
+            &roles,
+            &[Id::new(2)],
+            false
+        ));
+        assert!(!is_assignable(&roles[0], Id::new(1), &roles, &[], true));
+    }
+
+    #[test]
+    fn managed_role() {
+        let mut roles = roles();
+        roles[3].managed = true;
+        assert!(!is_assignable(
+            &roles[3],
+            Id::new(1),
+            &roles,
+            &[Id::new(2)],
+            false
+        ));
+        assert!(!is_assignable(&roles[3], Id::new(1), &roles, &[], true));
+    }
+
+    #[test]
+    fn guild_owner() {
+        let roles = roles();
+        assert!(is_assignable(&roles[1], Id::new(1), &roles, &[], true));
+    }
 }
@@ -0,0 +1,204 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use twilight_model::application::command::CommandOption;
+use twilight_model::application::command::CommandOptionChoice;
+
+use twilight_validate::command::CHOICES_LIMIT;
+
+use crate::Context;
+use crate::Error;
+
+/// The future returned by [`DynamicChoices::choices`].
+pub type ChoicesFuture<'a> = Pin<Box<dyn Future<Output = Vec<(String, i64)>> + Send + 'a>>;
+
+/// Choices for an integer option which aren't known until the commands are registered,
+/// such as the playlists which exist when the bot starts up.
+///
+/// This is the middle ground between a [`Choices`](crate::Choices) enum, which is fixed at compile time,
+/// and [autocomplete](crate::HandlerBuilder::autocomplete), which is queried every time the user types something.
+/// The choices are only fetched when registering commands, so they won't change until the next time the bot does that.
+///
+/// These are attached to an option with [`HandlerBuilder::dynamic_choices`](crate::HandlerBuilder::dynamic_choices),
+/// and the command receives whichever value the user picked as an `i64`.
+/// If the command has subcommands, every integer option with the given name inside them gets the choices.
+///
+/// # Examples
+/// ```no_run
+/// use twilight_interaction::{slash_command, ChoicesFuture, Context, DynamicChoices, Handler};
+///
+/// struct Playlists;
+///
+/// impl DynamicChoices for Playlists {
+///     fn choices<'a>(&'a self, _: &'a Context) -> ChoicesFuture<'a> {
+///         Box::pin(async {
+///             // Load these from somewhere, like a database.
+///             vec![("Favourites".to_string(), 1), ("Road trip".to_string(), 2)]
+///         })
+///     }
+/// }
+///
+/// #[slash_command(description("Plays a playlist", playlist = "The playlist to play"))]
+/// fn play(playlist: i64) -> String {
+///     format!("Playing playlist {}", playlist)
+/// }
+///
+/// # fn main() {
+/// let http = twilight_http::Client::new("my_token".to_string());
+///
+/// let builder = Handler::builder(http)
+///     .global_command(play::NAME, play::describe())
+///     .dynamic_choices(play::NAME, "playlist", Playlists);
+/// # }
+/// ```
+pub trait DynamicChoices<S = ()>: Send + Sync {
+    /// Fetches the choices, as pairs of the name shown to the user and the value sent to the command.
+    ///
+    /// `context` has the handler's HTTP client and state, and the guild the command is being registered in,
+    /// if it's a guild command. Since there's no interaction yet, its token and locale are empty.
+    ///
+    /// Discord allows at most 25 choices per option; returning more makes registering the commands fail
+    /// with [`Error::TooManyChoices`](crate::Error::TooManyChoices).
+    fn choices<'a>(&'a self, context: &'a Context<S>) -> ChoicesFuture<'a>;
+}
+
+/// Replaces the choices of the integer option named `option` in the command named `command`, whose options are `options`.
+///
+/// Options inside subcommands and subcommand groups are looked through as well,
+/// and all of the options named `option` get the choices.
+pub(crate) fn set_choices(
+    command: &str,
+    options: &mut [CommandOption],
+    option: &str,
+    choices: Vec<(String, i64)>,
+) -> Result<(), Error> {
+    if choices.len() > CHOICES_LIMIT {
+        return Err(Error::TooManyChoices {
+            command: command.to_string(),
+            option: option.to_string(),
+            count: choices.len(),
+        });
+    }
+
+    let choices: Vec<_> = choices
+        .into_iter()
+        .map(|(name, value)| CommandOptionChoice::Int { name, value })
+        .collect();
+
+    if replace_choices(options, option, &choices) {
+        Ok(())
+    } else {
+        Err(Error::UnknownChoicesOption {
+            command: command.to_string(),
+            option: option.to_string(),
+        })
+    }
+}
+
+/// Replaces the choices of every integer option named `name` in `options` with `choices`.
+///
+/// Returns whether any such options were found.
+fn replace_choices(
+    options: &mut [CommandOption],
+    name: &str,
+    choices: &[CommandOptionChoice],
+) -> bool {
+    let mut found = false;
+    for option in options {
+        match option {
+            CommandOption::Integer(data) if data.name == name => {
+                data.choices = choices.to_vec();
+                found = true;
+            }
+            CommandOption::SubCommand(data) | CommandOption::SubCommandGroup(data) => {
+                found |= replace_choices(&mut data.options, name, choices);
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::application::command::CommandOption;
+    use twilight_model::application::command::CommandOptionChoice;
+    use twilight_model::application::command::OptionsCommandOptionData;
+
+    use super::set_choices;
+    use crate::Error;
+    use crate::SlashCommandOption;
+
+    fn subcommand(name: &str, options: Vec<CommandOption>) -> CommandOption {
+        CommandOption::SubCommand(OptionsCommandOptionData {
+            description: "A subcommand".to_string(),
+            name: name.to_string(),
+            options,
+        })
+    }
+
+    fn choices(option: &CommandOption) -> &[CommandOptionChoice] {
+        match option {
+            CommandOption::Integer(data) => &data.choices,
+            _ => panic!("Not an integer option"),
+        }
+    }
+
+    fn playlist() -> CommandOption {
+        i64::describe("playlist".to_string(), "A playlist".to_string())
+    }
+
+    #[test]
+    fn subcommand_options() {
+        let mut options = vec![
+            subcommand("play", vec![playlist()]),
+            subcommand("delete", vec![playlist()]),
+        ];
+        set_choices(
+            "playlists",
+            &mut options,
+            "playlist",
+            vec![("Favourites".to_string(), 1)],
+        )
+        .unwrap();
+
+        for option in &options {
+            let options = match option {
+                CommandOption::SubCommand(data) => &data.options,
+                _ => unreachable!(),
+            };
+            assert_eq!(
+                choices(&options[0]),
+                [CommandOptionChoice::Int {
+                    name: "Favourites".to_string(),
+                    value: 1
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_option() {
+        let mut options = vec![subcommand("play", vec![playlist()])];
+        let result = set_choices("playlists", &mut options, "song", vec![]);
+        assert!(
+            matches!(result, Err(Error::UnknownChoicesOption { option, .. }) if option == "song")
+        );
+    }
+
+    #[test]
+    fn too_many_choices() {
+        let mut options = vec![playlist()];
+        let too_many = (0..26).map(|i| (i.to_string(), i)).collect();
+        let result = set_choices("play", &mut options, "playlist", too_many);
+        assert!(matches!(
+            result,
+            Err(Error::TooManyChoices { count: 26, .. })
+        ));
+        assert!(choices(&options[0]).is_empty());
+
+        let most = (0..25).map(|i| (i.to_string(), i)).collect();
+        set_choices("play", &mut options, "playlist", most).unwrap();
+        assert_eq!(choices(&options[0]).len(), 25);
+    }
+}
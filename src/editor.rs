@@ -0,0 +1,281 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use twilight_http::client::InteractionClient;
+use twilight_http::request::application::interaction::UpdateResponse;
+use twilight_http::Client;
+use twilight_model::channel::Message;
+use twilight_model::http::attachment::Attachment;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::Id;
+
+use crate::command_client;
+use crate::Error;
+use crate::IntoCallbackData;
+
+/// The minimum time between progress updates sent by a [`ResponseEditor`].
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A handle for managing the response to an interaction, created with [`Context::response_editor`](crate::Context::response_editor).
+///
+/// While a response is deferred, this can edit it before its future completes, which is useful for commands
+/// which take a while and want to show their progress, like a progress bar or text which is streamed in as it's generated.
+/// The output of the deferred future is still sent once it completes, and becomes the final version of the message.
+///
+/// It isn't tied to the command's handler, so it can also be passed off to something else,
+/// like a background job which edits the response or sends follow-ups long after the command finishes.
+/// Discord only accepts these requests for 15 minutes after the interaction.
+///
+/// All of these return [`Error::MissingClient`] if the handler was built without an HTTP client.
+///
+/// # Examples
+/// ```no_run
+/// use twilight_interaction::{slash_command, Context};
+///
+/// #[slash_command(description("Counts to 10, slowly"))]
+/// async fn count(ctx: Context) -> String {
+///     let mut editor = ctx.response_editor();
+///     for i in 1..10 {
+///         // Errors sending progress updates aren't fatal, since the final message is sent anyway.
+///         let _ = editor.update(i.to_string()).await;
+///         // ...wait for a bit...
+///     }
+///     "10".to_string()
+/// }
+///
+/// #[slash_command(description("Backs everything up"))]
+/// fn backup(ctx: Context) -> String {
+///     let editor = ctx.response_editor();
+///     tokio::spawn(async move {
+///         // ...do the backup...
+///         let _ = editor.edit("Backup finished!").await;
+///     });
+///     "Backing up...".to_string()
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct ResponseEditor {
+    http: Option<Arc<Client>>,
+    application_id: Option<Id<ApplicationMarker>>,
+    token: String,
+    last_update: Option<Instant>,
+}
+
+impl ResponseEditor {
+    pub(crate) fn new(
+        http: Option<Arc<Client>>,
+        application_id: Option<Id<ApplicationMarker>>,
+        token: String,
+    ) -> Self {
+        Self {
+            http,
+            application_id,
+            token,
+            last_update: None,
+        }
+    }
+
+    /// The token of the interaction whose response this manages.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Replaces the current version of the response with `data`, for showing progress.
+    ///
+    /// To keep clear of Discord's rate limits, updates made less than a second after the previous one are skipped,
+    /// in which case this returns `Ok(false)`. Since the deferred future's output is always sent,
+    /// this only ever drops intermediate versions of the message; use [`edit`](Self::edit) for changes which can't be skipped.
+    pub async fn update(&mut self, data: impl IntoCallbackData) -> Result<bool, Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+
+        if let Some(last_update) = self.last_update {
+            if last_update.elapsed() < MIN_UPDATE_INTERVAL {
+                return Ok(false);
+            }
+        }
+
+        self.last_update = Some(Instant::now());
+        update_original(&http, &self.token, data.into_callback_data(), &[]).await?;
+
+        Ok(true)
+    }
+
+    /// Replaces the original response with `data`.
+    pub async fn edit(&self, data: impl IntoCallbackData) -> Result<(), Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+        update_original(&http, &self.token, data.into_callback_data(), &[]).await
+    }
+
+    /// Deletes the original response.
+    pub async fn delete(&self) -> Result<(), Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+        http.delete_response(&self.token).exec().await?;
+        Ok(())
+    }
+
+    /// Sends `data` as a new message following on from the original response, returning the message.
+    ///
+    /// Unlike edits, follow-ups can be ephemeral (or not) regardless of the original response.
+    pub async fn follow_up(&self, data: impl IntoCallbackData) -> Result<Message, Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+        let data = data.into_callback_data();
+
+        let mut builder = http.create_followup(&self.token);
+
+        if let Some(content) = data.content.as_deref() {
+            builder = builder.content(content)?;
+        }
+
+        if let Some(embeds) = data.embeds.as_deref() {
+            builder = builder.embeds(embeds)?;
+        }
+
+        if let Some(components) = data.components.as_deref() {
+            builder = builder.components(components)?;
+        }
+
+        if let Some(allowed_mentions) = data.allowed_mentions.as_ref() {
+            builder = builder.allowed_mentions(Some(allowed_mentions));
+        }
+
+        if let Some(flags) = data.flags {
+            builder = builder.flags(flags);
+        }
+
+        if let Some(tts) = data.tts {
+            builder = builder.tts(tts);
+        }
+
+        Ok(builder.exec().await?.model().await?)
+    }
+}
+
+impl Debug for ResponseEditor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseEditor")
+            .field("http", &self.http)
+            .field("application_id", &self.application_id)
+            // The token lets anyone respond to the interaction, so keep it out of logs.
+            .field("token", &format_args!("<redacted>"))
+            .field("last_update", &self.last_update)
+            .finish()
+    }
+}
+
+/// Replaces the original response to the interaction with the token `token` with `callback`, attaching `attachments` to it.
+///
+/// Discord doesn't allow changing whether a message is text-to-speech or its flags when editing it,
+/// so `tts` and `flags` are ignored; whether a deferred response is ephemeral is decided by the initial response instead.
+pub(crate) async fn update_original(
+    http: &InteractionClient<'_>,
+    token: &str,
+    callback: InteractionResponseData,
+    attachments: &[Attachment],
+) -> Result<(), Error> {
+    update_request(http, token, &callback, attachments)?
+        .exec()
+        .await?;
+
+    Ok(())
+}
+
+/// Builds the request made by [`update_original`].
+fn update_request<'a>(
+    http: &'a InteractionClient<'_>,
+    token: &'a str,
+    callback: &'a InteractionResponseData,
+    attachments: &'a [Attachment],
+) -> Result<UpdateResponse<'a>, Error> {
+    let mut builder = http.update_response(token);
+
+    // Only set the fields which are actually present, so that sending just content doesn't clear any embeds and vice versa.
+    if let Some(content) = callback.content.as_deref() {
+        builder = builder.content(Some(content))?;
+    }
+
+    if let Some(embeds) = callback.embeds.as_deref() {
+        builder = builder.embeds(Some(embeds))?;
+    }
+
+    if let Some(components) = callback.components.as_deref() {
+        builder = builder.components(Some(components))?;
+    }
+
+    if let Some(allowed_mentions) = callback.allowed_mentions.as_ref() {
+        builder = builder.allowed_mentions(Some(allowed_mentions));
+    }
+
+    // Files have to be uploaded as a multipart request rather than plain JSON, which twilight switches to when there are any.
+    if !attachments.is_empty() {
+        builder = builder.attachments(attachments)?;
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_http::request::TryIntoRequest;
+    use twilight_http::Client;
+    use twilight_model::channel::embed::Embed;
+    use twilight_model::http::interaction::InteractionResponseData;
+    use twilight_model::id::Id;
+
+    use super::update_request;
+    use crate::EMPTY_CALLBACK;
+
+    /// Returns the JSON body of the request to update the original response to `callback`.
+    fn update_body(
+        callback: InteractionResponseData,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let client = Client::new(String::new());
+        let http = client.interaction(Id::new(1));
+        let request = update_request(&http, "token", &callback, &[])
+            .unwrap()
+            .try_into_request()
+            .unwrap();
+        serde_json::from_slice(request.body().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn update_content_only() {
+        let body = update_body(InteractionResponseData {
+            content: Some("Hello!".to_string()),
+            ..EMPTY_CALLBACK
+        });
+        assert_eq!(body["content"], "Hello!");
+        assert!(!body.contains_key("embeds"));
+        assert!(!body.contains_key("components"));
+    }
+
+    #[test]
+    fn update_embeds_only() {
+        let embed = Embed {
+            author: None,
+            color: None,
+            description: Some("An embed".to_string()),
+            fields: vec![],
+            footer: None,
+            image: None,
+            kind: "rich".to_string(),
+            provider: None,
+            thumbnail: None,
+            timestamp: None,
+            title: None,
+            url: None,
+            video: None,
+        };
+        let body = update_body(InteractionResponseData {
+            embeds: Some(vec![embed]),
+            ..EMPTY_CALLBACK
+        });
+        assert_eq!(body["embeds"][0]["description"], "An embed");
+        assert!(!body.contains_key("content"));
+    }
+}
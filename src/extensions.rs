@@ -0,0 +1,63 @@
+use std::any::Any;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// Extra data attached to a single interaction by whatever passed it to the handler,
+/// such as the ID of the shard it was received on.
+///
+/// Each value is stored by its type, so there can only be one of each type;
+/// wrap values in a newtype to tell them apart.
+/// They're available to commands through [`Context::extension`](crate::Context::extension).
+///
+/// # Examples
+/// ```
+/// use twilight_interaction::Extensions;
+///
+/// struct ShardId(u64);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(ShardId(3));
+///
+/// assert_eq!(extensions.get::<ShardId>().map(|shard| shard.0), Some(3));
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+    // These are `Arc`s so that `Context` stays cheap to clone.
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Creates an empty set of extensions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `value`, replacing any existing value of the same type.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) {
+        self.map.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Gets the value of type `T`, if there is one.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
@@ -1,191 +1,853 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::HashSet;
+#[cfg(feature = "webhook")]
+use std::fmt;
+#[cfg(feature = "webhook")]
+use std::fmt::Debug;
+#[cfg(feature = "webhook")]
+use std::fmt::Formatter;
 use std::future::Future;
+#[cfg(feature = "webhook")]
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+#[cfg(feature = "webhook")]
+use std::task;
+#[cfg(feature = "webhook")]
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
 
+use twilight_http::client::InteractionClient;
 use twilight_http::Client;
-use twilight_model::application::callback::CallbackData;
-use twilight_model::application::callback::InteractionResponse;
+use twilight_model::application::command::permissions::CommandPermissions;
+use twilight_model::application::command::Command;
+use twilight_model::application::command::CommandOptionChoice;
+use twilight_model::application::command::CommandType;
 use twilight_model::application::interaction::application_command::CommandData;
-use twilight_model::application::interaction::message_component::MessageComponentInteractionData;
 use twilight_model::application::interaction::Interaction;
+use twilight_model::application::interaction::InteractionType;
+use twilight_model::channel::message::AllowedMentions;
 use twilight_model::channel::message::MessageFlags;
 use twilight_model::channel::Message;
-use twilight_model::id::CommandId;
-use twilight_model::id::GuildId;
+use twilight_model::guild::PartialMember;
+use twilight_model::http::interaction::InteractionResponse;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::http::interaction::InteractionResponseType;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::marker::CommandMarker;
+use twilight_model::id::marker::GuildMarker;
+#[cfg(feature = "webhook")]
+use twilight_model::id::marker::InteractionMarker;
+use twilight_model::id::marker::UserMarker;
+use twilight_model::id::Id;
+use twilight_model::user::User;
 
+use crate::set_choices;
+#[cfg(any(feature = "gateway", feature = "webhook"))]
+use crate::update_original;
+use crate::AsyncSlashHandlerFn;
+use crate::AutocompleteHandlerFn;
+use crate::AutocompleteInput;
 use crate::CommandDecl;
+use crate::CommandResponse;
+use crate::CommandScope;
+use crate::CommandSpec;
+use crate::ComponentData;
+use crate::ComponentHandlerFn;
 use crate::ComponentResponse;
 use crate::Context;
 use crate::DeferredFuture;
+use crate::DynamicChoices;
 use crate::Error;
+use crate::ErrorHandlerFn;
+use crate::Extensions;
+use crate::HandleError;
+use crate::IntoCallbackData;
 use crate::MessageHandlerFn;
+use crate::OptionError;
+use crate::OptionErrorReason;
 use crate::Response;
 use crate::SlashHandlerFn;
+use crate::UnknownCommandHandlerFn;
 use crate::UserHandlerFn;
 use crate::EMPTY_CALLBACK;
 
 /// The information needed to actually handle a command.
-enum CommandHandler {
-    Slash(SlashHandlerFn),
-    Message(MessageHandlerFn),
-    User(UserHandlerFn),
+enum CommandHandler<S> {
+    Slash(SlashHandlerFn<S>),
+    /// The handler, and whether to defer its response ephemerally when it can't be awaited.
+    AsyncSlash(AsyncSlashHandlerFn<S>, bool),
+    Message(MessageHandlerFn<S>),
+    User(UserHandlerFn<S>),
 }
 
-impl CommandHandler {
+impl<S> CommandHandler<S> {
+    fn kind(&self) -> CommandType {
+        match self {
+            Self::Slash(_) | Self::AsyncSlash(..) => CommandType::ChatInput,
+            Self::Message(_) => CommandType::Message,
+            Self::User(_) => CommandType::User,
+        }
+    }
+
     fn handle(
         &self,
-        context: Context,
+        context: Context<S>,
         data: CommandData,
-    ) -> (InteractionResponse, Option<DeferredFuture>) {
+        error_handler: &ErrorHandlerFn,
+    ) -> Result<(InteractionResponse, Option<DeferredFuture>), HandleError> {
         match self {
             Self::Slash(handler) => {
-                handler(context, data.options, data.resolved).unwrap_or_else(|err| {
-                    (
-                        InteractionResponse::ChannelMessageWithSource(CallbackData {
-                            content: Some(format!("Invalid option '{}'", err)),
-                            flags: Some(MessageFlags::EPHEMERAL),
+                let command = data.name;
+                handler(context, data.options, data.resolved)
+                    .map_err(|errors| HandleError::InvalidOption { command, errors })
+            }
+            // The handler can't be awaited here, so the response has to be deferred and then filled in with whatever it returns.
+            Self::AsyncSlash(handler, ephemeral) => {
+                let command = data.name;
+                let future = handler(context, data.options, data.resolved);
+                let error_handler = Arc::clone(error_handler);
+                let deferred: DeferredFuture = Box::pin(async move {
+                    match future.await {
+                        Ok((_, Some(future))) => future.await,
+                        Ok((
+                            InteractionResponse {
+                                kind: InteractionResponseType::ChannelMessageWithSource,
+                                data: Some(data),
+                            },
+                            None,
+                        )) => data.into(),
+                        // The response was already deferred as a message, so it's too late to respond any other way.
+                        Ok((response, None)) => error_handler(&HandleError::UnsupportedResponse {
+                            command,
+                            kind: response.kind,
+                        })
+                        .into(),
+                        Err(errors) => {
+                            error_handler(&HandleError::InvalidOption { command, errors }).into()
+                        }
+                    }
+                });
+                Ok((
+                    InteractionResponse {
+                        kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                        data: Some(InteractionResponseData {
+                            flags: if *ephemeral {
+                                Some(MessageFlags::EPHEMERAL)
+                            } else {
+                                None
+                            },
                             ..EMPTY_CALLBACK
                         }),
-                        None,
-                    )
-                })
+                    },
+                    Some(deferred),
+                ))
             }
-            // These two are implemented a bit hackily; twilight doesn't expose `target_id` yet,
-            // so we have to exploit the fact that the user/message being targeted is the only thing in resolved (hopefully!)
+            // The targeted message or user is picked out of the resolved data using `target_id`,
+            // since the resolved data might include other things too.
             Self::Message(handler) => data
-                .resolved
-                .filter(|resolved| resolved.messages.len() == 1)
-                .and_then(|mut resolved| resolved.messages.pop())
+                .target_id
+                .zip(data.resolved)
+                .and_then(|(target_id, mut resolved)| resolved.messages.remove(&target_id.cast()))
                 .map(|message| handler(context, message))
-                .unwrap_or_else(|| {
-                    (
-                        InteractionResponse::ChannelMessageWithSource(CallbackData {
-                            content: Some("Invalid message command recieved".to_string()),
-                            flags: Some(MessageFlags::EPHEMERAL),
-                            ..EMPTY_CALLBACK
-                        }),
-                        None,
-                    )
-                }),
+                .ok_or(HandleError::InvalidMessageCommand),
             Self::User(handler) => data
-                .resolved
-                .filter(|resolved| resolved.users.len() == 1)
-                .and_then(|mut resolved| resolved.users.pop())
+                .target_id
+                .zip(data.resolved)
+                .and_then(|(target_id, mut resolved)| resolved.users.remove(&target_id.cast()))
                 .map(|user| handler(context, user))
-                .unwrap_or_else(|| {
-                    (
-                        InteractionResponse::ChannelMessageWithSource(CallbackData {
-                            content: Some("Invalid user command recieved".to_string()),
-                            flags: Some(MessageFlags::EPHEMERAL),
-                            ..EMPTY_CALLBACK
-                        }),
-                        None,
-                    )
-                }),
+                .ok_or(HandleError::InvalidUserCommand),
         }
     }
 }
 
-impl From<CommandDecl> for CommandHandler {
-    fn from(decl: CommandDecl) -> Self {
+impl<S> From<CommandDecl<S>> for CommandHandler<S> {
+    fn from(decl: CommandDecl<S>) -> Self {
         match decl {
             CommandDecl::Slash { handler, .. } => Self::Slash(handler),
-            CommandDecl::Message { handler } => Self::Message(handler),
-            CommandDecl::User { handler } => Self::User(handler),
+            CommandDecl::AsyncSlash {
+                handler, ephemeral, ..
+            } => Self::AsyncSlash(handler, ephemeral),
+            CommandDecl::Message { handler, .. } => Self::Message(handler),
+            CommandDecl::User { handler, .. } => Self::User(handler),
         }
     }
 }
 
-pub struct Handler {
-    http: Client,
-    command_handlers: Vec<(CommandId, CommandHandler)>,
-    component_handler: Option<
-        Box<
-            dyn Fn(Context, Message, MessageComponentInteractionData) -> ComponentResponse
-                + Send
-                + Sync,
-        >,
-    >,
+/// Handles incoming interactions, dispatching them to the appropriate command.
+///
+/// `S` is the type of the state shared between commands, which is available through [`Context::state`].
+pub struct Handler<S = ()> {
+    http: Option<Arc<Client>>,
+    application_id: Option<Id<ApplicationMarker>>,
+    state: S,
+    command_handlers: Vec<(
+        Id<CommandMarker>,
+        CommandScope,
+        Cow<'static, str>,
+        CommandHandler<S>,
+    )>,
+    component_handlers: HashMap<&'static str, ComponentHandlerFn<S>>,
+    component_handler: Option<ComponentHandlerFn<S>>,
+    unknown_command_handler: Option<UnknownCommandHandlerFn<S>>,
+    autocomplete_handlers: HashMap<Cow<'static, str>, AutocompleteHandlerFn<S>>,
+    /// The guilds that restricted commands are allowed to be used in.
+    restrictions: HashMap<Id<CommandMarker>, HashSet<Id<GuildMarker>>>,
+    /// The response to give when a restricted command is used outside of its allowed guilds.
+    restricted_response: InteractionResponseData,
+    /// How long users have to wait between uses of commands with cooldowns.
+    cooldowns: HashMap<Id<CommandMarker>, Duration>,
+    /// When each user last used each command with a cooldown.
+    last_used: Mutex<LastUsed>,
+    /// Creates the response to give when an interaction can't be handled.
+    error_handler: ErrorHandlerFn,
+    /// The allowed mentions to use for responses which don't set their own.
+    allowed_mentions: Option<AllowedMentions>,
+    /// How long deferred responses can take before being replaced with an error message.
+    #[cfg(feature = "tokio")]
+    deferred_timeout: Duration,
+}
+
+/// The fewest uses [`LastUsed`] holds before it starts clearing out ones whose cooldowns have run out.
+const MIN_PRUNE_AT: usize = 64;
+
+/// When each user last used each command with a cooldown.
+struct LastUsed {
+    uses: HashMap<(Id<CommandMarker>, Id<UserMarker>), Instant>,
+    /// How many uses there can be before the ones whose cooldowns have run out are cleared out.
+    prune_at: usize,
+}
+
+impl Default for LastUsed {
+    fn default() -> Self {
+        LastUsed {
+            uses: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        }
+    }
 }
 
 impl Handler {
-    pub fn builder(http: Client) -> HandlerBuilder {
+    /// Creates a builder for a handler without any shared state.
+    ///
+    /// `http` can be an `Arc<Client>`, to share the client with other parts of the bot like the gateway.
+    pub fn builder(http: impl Into<Arc<Client>>) -> HandlerBuilder {
+        Handler::builder_with_state(http, ())
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> Handler<S> {
+    /// Creates a builder for a handler whose commands all have access to `state` through [`Context::state`].
+    ///
+    /// `state` is cloned for every interaction, so it should usually be something cheap to clone like an `Arc`.
+    pub fn builder_with_state(http: impl Into<Arc<Client>>, state: S) -> HandlerBuilder<S> {
+        HandlerBuilder {
+            http: Some(http.into()),
+            ..Handler::builder_without_client(state)
+        }
+    }
+
+    /// Creates a builder for a handler without an HTTP client, for webhook deployments which don't need one,
+    /// like serverless functions whose commands are registered separately.
+    ///
+    /// Immediate responses are returned in the body of the webhook response, so they work as normal. However:
+    /// - Commands can't be registered, so the handler has to be built with
+    ///   [`build_with_ids`](HandlerBuilder::build_with_ids) or [`build_offline`](HandlerBuilder::build_offline).
+    /// - Deferred responses can't be sent; their futures fail with [`Error::MissingClient`].
+    /// - [`Context::http`] returns `None`, and anything else needing the client returns [`Error::MissingClient`].
+    pub fn builder_without_client(state: S) -> HandlerBuilder<S> {
         HandlerBuilder {
             global_commands: Vec::new(),
             guild_commands: HashMap::new(),
+            component_handlers: HashMap::new(),
             component_handler: None,
-            http,
+            unknown_command_handler: None,
+            autocomplete_handlers: HashMap::new(),
+            dynamic_choices: Vec::new(),
+            builtin_ping: false,
+            dev_guild: None,
+            restrictions: HashMap::new(),
+            cooldowns: HashMap::new(),
+            command_permissions: HashMap::new(),
+            restricted_response: InteractionResponseData {
+                content: Some("This command isn't available here".to_string()),
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..EMPTY_CALLBACK
+            },
+            error_handler: Arc::new(|err| InteractionResponseData {
+                content: Some(err.to_string()),
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..EMPTY_CALLBACK
+            }),
+            allowed_mentions: None,
+            #[cfg(feature = "tokio")]
+            deferred_timeout: DEFAULT_DEFERRED_TIMEOUT,
+            http: None,
+            application_id: None,
+            state,
         }
     }
 
-    fn context(&self) -> Context {
+    fn context(
+        &self,
+        application_id: Id<ApplicationMarker>,
+        guild_id: Option<Id<GuildMarker>>,
+        user_id: Option<Id<UserMarker>>,
+        token: &str,
+        locale: &str,
+        extensions: Extensions,
+    ) -> Context<S> {
         Context {
             http: self.http.clone(),
+            application_id: Some(application_id),
+            state: self.state.clone(),
+            guild_id,
+            user_id,
+            token: token.to_string(),
+            locale: locale.to_string(),
+            extensions,
         }
     }
 
     pub fn handle(&self, interaction: Interaction) -> Response {
+        self.handle_with(interaction, Extensions::new())
+    }
+
+    /// Handles each of `interactions` in order, returning their responses in the same order.
+    ///
+    /// This is mostly useful for testing a handler built with [`build_offline`](HandlerBuilder::build_offline)
+    /// against a scripted sequence of interactions. Deferred futures aren't run; take them from the responses to do that.
+    pub fn handle_all(&self, interactions: impl IntoIterator<Item = Interaction>) -> Vec<Response> {
+        interactions
+            .into_iter()
+            .map(|interaction| self.handle(interaction))
+            .collect()
+    }
+
+    /// Like [`handle`](Self::handle), but attaches `extensions` to the interaction,
+    /// which handlers can get through [`Context::extension`].
+    ///
+    /// This is useful for passing along things only the caller knows, like the shard the interaction was received on.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, interaction, extensions), fields(id, command))
+    )]
+    pub fn handle_with(&self, interaction: Interaction, extensions: Extensions) -> Response {
+        #[cfg(feature = "tracing")]
+        let started = Instant::now();
+
+        #[cfg(feature = "metrics")]
+        record_interaction(&interaction);
+
+        let response = self.dispatch(interaction, extensions);
+
+        #[cfg(feature = "tracing")]
+        {
+            tracing::Span::current().record("id", response.id.get());
+            tracing::debug!(
+                latency = ?started.elapsed(),
+                deferred = response.future.is_some(),
+                "handled interaction"
+            );
+        }
+
+        self.apply_allowed_mentions(self.apply_deferred_timeout(response))
+    }
+
+    /// Like [`handle`](Self::handle), but awaits the handlers of commands declared with [`CommandDecl::slash_async`],
+    /// instead of deferring their responses. This lets them respond immediately, as long as they do so
+    /// within the 3 seconds Discord allows.
+    ///
+    /// Everything else is handled exactly the same as with `handle`.
+    pub async fn handle_async(&self, interaction: Interaction) -> Response {
+        self.handle_async_with(interaction, Extensions::new()).await
+    }
+
+    /// Like [`handle_async`](Self::handle_async), but attaches `extensions` to the interaction,
+    /// which handlers can get through [`Context::extension`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, interaction, extensions), fields(id, command))
+    )]
+    pub async fn handle_async_with(
+        &self,
+        interaction: Interaction,
+        extensions: Extensions,
+    ) -> Response {
+        #[cfg(feature = "tracing")]
+        let started = Instant::now();
+
+        #[cfg(feature = "metrics")]
+        record_interaction(&interaction);
+
+        let response = self.dispatch_async(interaction, extensions).await;
+
+        #[cfg(feature = "tracing")]
+        {
+            tracing::Span::current().record("id", response.id.get());
+            tracing::debug!(
+                latency = ?started.elapsed(),
+                deferred = response.future.is_some(),
+                "handled interaction"
+            );
+        }
+
+        self.apply_allowed_mentions(self.apply_deferred_timeout(response))
+    }
+
+    /// Like [`dispatch`](Self::dispatch), but awaits the handlers of commands declared with [`CommandDecl::slash_async`].
+    async fn dispatch_async(&self, interaction: Interaction, extensions: Extensions) -> Response {
+        let command = match interaction {
+            Interaction::ApplicationCommand(command) => command,
+            interaction => return self.dispatch(interaction, extensions),
+        };
+
+        // Restricted commands are left to `dispatch`, so that they get the usual response.
+        let handler = self
+            .command_handlers
+            .iter()
+            .find_map(|(id, _, _, handler)| match handler {
+                CommandHandler::AsyncSlash(handler, _)
+                    if *id == command.data.id && !self.is_restricted(*id, command.guild_id) =>
+                {
+                    Some(handler)
+                }
+                _ => None,
+            });
+        let handler = match handler {
+            Some(handler) => handler,
+            None => return self.dispatch(Interaction::ApplicationCommand(command), extensions),
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("command", command.data.name.as_str());
+
+        let user_id = author_id(command.member.as_ref(), command.user.as_ref());
+        if let Err(err) = self.check_cooldown(command.data.id, user_id) {
+            return Response {
+                response: self.error_response(&err),
+                future: None,
+                id: command.id,
+                token: command.token,
+                application_id: command.application_id,
+            };
+        }
+
+        let context = self.context(
+            command.application_id,
+            command.guild_id,
+            user_id,
+            &command.token,
+            &command.locale,
+            extensions,
+        );
+        let (response, future) =
+            match handler(context, command.data.options, command.data.resolved).await {
+                Ok(response) => {
+                    self.record_use(command.data.id, user_id);
+                    response
+                }
+                Err(errors) => {
+                    let err = HandleError::InvalidOption {
+                        command: command.data.name,
+                        errors,
+                    };
+                    (self.error_response(&err), None)
+                }
+            };
+
+        Response {
+            response,
+            future,
+            id: command.id,
+            token: command.token,
+            application_id: command.application_id,
+        }
+    }
+
+    /// Fills in [`HandlerBuilder::allowed_mentions`] on `response` and the output of its deferred future,
+    /// if they don't have allowed mentions of their own.
+    fn apply_allowed_mentions(&self, mut response: Response) -> Response {
+        let allowed_mentions = match &self.allowed_mentions {
+            Some(allowed_mentions) => allowed_mentions.clone(),
+            None => return response,
+        };
+
+        if let (
+            InteractionResponseType::ChannelMessageWithSource
+            | InteractionResponseType::UpdateMessage,
+            Some(data),
+        ) = (response.response.kind, response.response.data.as_mut())
+        {
+            data.allowed_mentions
+                .get_or_insert_with(|| allowed_mentions.clone());
+        }
+
+        response.future = response.future.map(|future| -> DeferredFuture {
+            Box::pin(async move {
+                let mut output = future.await;
+                output.data.allowed_mentions.get_or_insert(allowed_mentions);
+                output
+            })
+        });
+
+        response
+    }
+
+    /// Makes `response`'s deferred future give up after [`HandlerBuilder::deferred_timeout`], if it has one.
+    #[allow(unused_mut)]
+    fn apply_deferred_timeout(&self, mut response: Response) -> Response {
+        #[cfg(feature = "tokio")]
+        {
+            let timeout = self.deferred_timeout;
+            response.future = response
+                .future
+                .map(|future| with_timeout(future, timeout, Arc::clone(&self.error_handler)));
+        }
+
+        response
+    }
+
+    /// Returns [`HandleError::OnCooldown`] if the user `user_id` used the command `id` too recently.
+    fn check_cooldown(
+        &self,
+        id: Id<CommandMarker>,
+        user_id: Option<Id<UserMarker>>,
+    ) -> Result<(), HandleError> {
+        let (cooldown, user_id) = match (self.cooldowns.get(&id), user_id) {
+            (Some(&cooldown), Some(user_id)) => (cooldown, user_id),
+            _ => return Ok(()),
+        };
+
+        let last_used = self
+            .last_used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let elapsed = match last_used.uses.get(&(id, user_id)) {
+            Some(used) => used.elapsed(),
+            None => return Ok(()),
+        };
+        if elapsed < cooldown {
+            Err(HandleError::OnCooldown {
+                remaining: cooldown - elapsed,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records that the user `user_id` just used the command `id`, if it has a cooldown.
+    ///
+    /// This is only done once the command's options have been parsed successfully,
+    /// so that a typo doesn't leave the user waiting out the cooldown for nothing.
+    fn record_use(&self, id: Id<CommandMarker>, user_id: Option<Id<UserMarker>>) {
+        let user_id = match user_id {
+            Some(user_id) if self.cooldowns.contains_key(&id) => user_id,
+            _ => return,
+        };
+
+        let mut last_used = self
+            .last_used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        last_used.uses.insert((id, user_id), Instant::now());
+
+        // Forget about any uses whose cooldowns have run out, so that this doesn't grow forever.
+        // This only happens each time the number of uses doubles, so that it doesn't have to go through all of them every time.
+        if last_used.uses.len() >= last_used.prune_at {
+            let cooldowns = &self.cooldowns;
+            last_used.uses.retain(|(id, _), used| {
+                cooldowns
+                    .get(id)
+                    .is_some_and(|&cooldown| used.elapsed() < cooldown)
+            });
+            last_used.prune_at = (last_used.uses.len() * 2).max(MIN_PRUNE_AT);
+        }
+    }
+
+    /// Whether the command `id` can't be used in `guild_id`, because it's been restricted to other guilds with [`HandlerBuilder::restrict`].
+    fn is_restricted(&self, id: Id<CommandMarker>, guild_id: Option<Id<GuildMarker>>) -> bool {
+        self.restrictions.get(&id).is_some_and(|allowed_guilds| {
+            !guild_id.is_some_and(|guild_id| allowed_guilds.contains(&guild_id))
+        })
+    }
+
+    /// The name, ID and type of every command this handler handles, including the built-in ping command if it's enabled.
+    ///
+    /// This is useful for logging, or setting up command permissions with the IDs Discord assigned during registration.
+    pub fn commands(&self) -> impl Iterator<Item = (&str, Id<CommandMarker>, CommandType)> {
+        self.command_handlers
+            .iter()
+            .map(|(id, _, name, handler)| (name.as_ref(), *id, handler.kind()))
+    }
+
+    /// Sets who can use the command(s) named `name` in the guild `guild_id`,
+    /// replacing any permissions they had there before.
+    ///
+    /// If both a global command and a command registered in `guild_id` are named `name`, both are updated.
+    ///
+    /// Returns [`Error::UnknownCommand`] if this handler doesn't have a command named `name`
+    /// which can be used in `guild_id`, or [`Error::MissingClient`] if it was built without an HTTP client.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, permissions), err))]
+    pub async fn set_command_permissions(
+        &self,
+        guild_id: Id<GuildMarker>,
+        name: &str,
+        permissions: &[CommandPermissions],
+    ) -> Result<(), Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+
+        let ids = self.command_ids_in(guild_id, name);
+        if ids.is_empty() {
+            return Err(Error::UnknownCommand {
+                name: name.to_string(),
+            });
+        }
+
+        for id in ids {
+            http.update_command_permissions(guild_id, id, permissions)?
+                .exec()
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// The IDs of the commands named `name` which can be used in `guild_id`;
+    /// that is, ones registered globally or in that guild, but not other guilds.
+    fn command_ids_in(&self, guild_id: Id<GuildMarker>, name: &str) -> Vec<Id<CommandMarker>> {
+        self.command_handlers
+            .iter()
+            .filter(|(_, scope, command_name, _)| {
+                command_name == name
+                    && match *scope {
+                        CommandScope::Global => true,
+                        CommandScope::Guild(scope_guild_id) => scope_guild_id == guild_id,
+                    }
+            })
+            .map(|(id, _, _, _)| *id)
+            .collect()
+    }
+
+    /// Deletes the commands this handler registered from Discord, and stops handling them.
+    ///
+    /// Only the commands this handler knows the IDs of are deleted, so commands registered
+    /// by other handlers or manually are left alone. This is useful for temporary deployments,
+    /// like test runs or preview environments, so that their commands don't pile up.
+    ///
+    /// This has to be called explicitly, since deleting the commands requires making HTTP requests,
+    /// which can't be done when the handler is dropped.
+    /// It isn't meaningful for handlers built with [`build_offline`](HandlerBuilder::build_offline),
+    /// whose command IDs are made up.
+    ///
+    /// Returns [`Error::MissingClient`] if the handler was built without an HTTP client.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self), err))]
+    pub async fn deregister(self) -> Result<(), Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+
+        for (id, scope, _, _) in &self.command_handlers {
+            match *scope {
+                CommandScope::Global => {
+                    http.delete_global_command(*id).exec().await?;
+                }
+                CommandScope::Guild(guild_id) => {
+                    http.delete_guild_command(guild_id, *id).exec().await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Passes `interaction` to the appropriate handler.
+    fn dispatch(&self, interaction: Interaction, extensions: Extensions) -> Response {
         match interaction {
             Interaction::Ping(ping) => Response {
-                response: InteractionResponse::Pong,
+                response: InteractionResponse {
+                    kind: InteractionResponseType::Pong,
+                    data: None,
+                },
                 future: None,
                 id: ping.id,
                 token: ping.token,
+                application_id: ping.application_id,
             },
             Interaction::ApplicationCommand(command) => {
-                for (id, handler) in &self.command_handlers {
+                #[cfg(feature = "tracing")]
+                tracing::Span::current().record("command", command.data.name.as_str());
+
+                for (id, _, _, handler) in &self.command_handlers {
                     if command.data.id == *id {
-                        let (response, future) = handler.handle(self.context(), command.data);
+                        if self.is_restricted(*id, command.guild_id) {
+                            return Response {
+                                response: InteractionResponse {
+                                    kind: InteractionResponseType::ChannelMessageWithSource,
+                                    data: Some(self.restricted_response.clone()),
+                                },
+                                future: None,
+                                id: command.id,
+                                token: command.token,
+                                application_id: command.application_id,
+                            };
+                        }
+
+                        let user_id = author_id(command.member.as_ref(), command.user.as_ref());
+                        if let Err(err) = self.check_cooldown(*id, user_id) {
+                            return Response {
+                                response: self.error_response(&err),
+                                future: None,
+                                id: command.id,
+                                token: command.token,
+                                application_id: command.application_id,
+                            };
+                        }
+
+                        let result = handler.handle(
+                            self.context(
+                                command.application_id,
+                                command.guild_id,
+                                user_id,
+                                &command.token,
+                                &command.locale,
+                                extensions,
+                            ),
+                            command.data,
+                            &self.error_handler,
+                        );
+                        let (response, future) = match result {
+                            Ok(response) => {
+                                self.record_use(*id, user_id);
+                                response
+                            }
+                            Err(err) => (self.error_response(&err), None),
+                        };
 
                         return Response {
                             response,
                             future,
                             id: command.id,
                             token: command.token,
+                            application_id: command.application_id,
                         };
                     }
                 }
 
-                // It didn't match any known commands, so give an error response.
+                // It didn't match any known commands, so pass it to the fallback handler if there is one,
+                // and otherwise give an error response.
+                let (response, future) = match &self.unknown_command_handler {
+                    Some(handler) => handler(
+                        self.context(
+                            command.application_id,
+                            command.guild_id,
+                            author_id(command.member.as_ref(), command.user.as_ref()),
+                            &command.token,
+                            &command.locale,
+                            extensions,
+                        ),
+                        command.data,
+                    ),
+                    None => (
+                        self.error_response(&HandleError::UnknownCommand {
+                            name: command.data.name,
+                        }),
+                        None,
+                    ),
+                };
+
                 Response {
-                    response: InteractionResponse::ChannelMessageWithSource(CallbackData {
-                        content: Some(format!("Unknown command '/{}'", command.data.name)),
-                        flags: Some(MessageFlags::EPHEMERAL),
-                        ..EMPTY_CALLBACK
-                    }),
-                    future: None,
+                    response,
+                    future,
                     id: command.id,
                     token: command.token,
+                    application_id: command.application_id,
                 }
             }
             Interaction::MessageComponent(interaction) => {
-                let (response, future) = if let Some(handler) = &self.component_handler {
-                    let response = handler(self.context(), interaction.message, interaction.data);
+                // Handlers for a specific `custom_id` take priority over the catch-all handler.
+                let handler = self
+                    .component_handlers
+                    .get(interaction.data.custom_id.as_str())
+                    .or(self.component_handler.as_ref());
+
+                let (response, future) = if let Some(handler) = handler {
+                    let response = handler(
+                        self.context(
+                            interaction.application_id,
+                            interaction.guild_id,
+                            author_id(interaction.member.as_ref(), interaction.user.as_ref()),
+                            &interaction.token,
+                            &interaction.locale,
+                            extensions,
+                        ),
+                        interaction.message,
+                        interaction.data.into(),
+                    );
                     match response {
-                        ComponentResponse::Message(data) => {
-                            (InteractionResponse::ChannelMessageWithSource(data), None)
+                        ComponentResponse::Message(data) => (
+                            InteractionResponse {
+                                kind: InteractionResponseType::ChannelMessageWithSource,
+                                data: Some(data),
+                            },
+                            None,
+                        ),
+                        ComponentResponse::EphemeralMessage(data) => {
+                            let flags = data.flags.unwrap_or_else(MessageFlags::empty);
+                            (
+                                InteractionResponse {
+                                    kind: InteractionResponseType::ChannelMessageWithSource,
+                                    data: Some(InteractionResponseData {
+                                        flags: Some(flags | MessageFlags::EPHEMERAL),
+                                        ..data
+                                    }),
+                                },
+                                None,
+                            )
                         }
-                        ComponentResponse::DeferredMessage(future) => (
-                            InteractionResponse::DeferredChannelMessageWithSource(EMPTY_CALLBACK),
+                        ComponentResponse::DeferredMessage { future, ephemeral } => (
+                            InteractionResponse {
+                                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                                data: Some(InteractionResponseData {
+                                    flags: if ephemeral {
+                                        Some(MessageFlags::EPHEMERAL)
+                                    } else {
+                                        None
+                                    },
+                                    ..EMPTY_CALLBACK
+                                }),
+                            },
                             Some(future),
                         ),
-                        ComponentResponse::Update(data) => {
-                            (InteractionResponse::UpdateMessage(data), None)
-                        }
-                        ComponentResponse::DeferredUpdate(future) => {
-                            (InteractionResponse::DeferredUpdateMessage, Some(future))
+                        ComponentResponse::Update(mut data) => {
+                            // Whether a message is ephemeral can't be changed after it's sent,
+                            // so don't let Discord reject the update over it.
+                            data.flags = data
+                                .flags
+                                .map(|flags| flags - MessageFlags::EPHEMERAL)
+                                .filter(|flags| !flags.is_empty());
+                            (
+                                InteractionResponse {
+                                    kind: InteractionResponseType::UpdateMessage,
+                                    data: Some(data),
+                                },
+                                None,
+                            )
                         }
+                        ComponentResponse::DeferredUpdate(future) => (
+                            InteractionResponse {
+                                kind: InteractionResponseType::DeferredUpdateMessage,
+                                data: None,
+                            },
+                            Some(future),
+                        ),
                     }
                 } else {
-                    (
-                        InteractionResponse::ChannelMessageWithSource(CallbackData {
-                            content: Some(
-                                "Error: no message component handler registered".to_string(),
-                            ),
-                            flags: Some(MessageFlags::EPHEMERAL),
-                            ..EMPTY_CALLBACK
-                        }),
-                        None,
-                    )
+                    let err = HandleError::UnknownComponent {
+                        custom_id: interaction.data.custom_id,
+                    };
+                    (self.error_response(&err), None)
                 };
 
                 Response {
@@ -193,72 +855,213 @@ impl Handler {
                     future,
                     id: interaction.id,
                     token: interaction.token,
+                    application_id: interaction.application_id,
+                }
+            }
+            Interaction::ApplicationCommandAutocomplete(autocomplete) => {
+                #[cfg(feature = "tracing")]
+                tracing::Span::current().record("command", autocomplete.data.name.as_str());
+
+                // This has to be created before the options are moved out of `autocomplete`.
+                let context = self.context(
+                    autocomplete.application_id,
+                    autocomplete.guild_id,
+                    author_id(autocomplete.member.as_ref(), autocomplete.user.as_ref()),
+                    &autocomplete.token,
+                    &autocomplete.locale,
+                    extensions,
+                );
+                let choices = self
+                    .autocomplete_handlers
+                    .get(autocomplete.data.name.as_str())
+                    .zip(AutocompleteInput::parse(autocomplete.data.options))
+                    .map(|(handler, input)| handler(context, input))
+                    .unwrap_or_default();
+
+                Response {
+                    response: InteractionResponse {
+                        kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+                        data: Some(InteractionResponseData {
+                            choices: Some(choices),
+                            ..EMPTY_CALLBACK
+                        }),
+                    },
+                    future: None,
+                    id: autocomplete.id,
+                    token: autocomplete.token,
+                    application_id: autocomplete.application_id,
+                }
+            }
+            Interaction::ModalSubmit(modal) => {
+                let err = HandleError::UnsupportedInteraction {
+                    kind: InteractionType::ModalSubmit,
+                };
+                Response {
+                    response: self.error_response(&err),
+                    future: None,
+                    id: modal.id,
+                    application_id: modal.application_id,
+                    token: modal.token,
+                }
+            }
+            // Interactions added after this version of twilight-model don't expose their token through `Interaction`,
+            // so the response can only be delivered through a webhook, which doesn't need it.
+            interaction => {
+                let err = HandleError::UnsupportedInteraction {
+                    kind: interaction.kind(),
+                };
+                Response {
+                    response: self.error_response(&err),
+                    future: None,
+                    id: interaction.id(),
+                    application_id: interaction.application_id(),
+                    token: String::new(),
                 }
             }
-            _ => todo!(),
+        }
+    }
+
+    /// The response to give when an interaction can't be handled because of `err`.
+    fn error_response(&self, err: &HandleError) -> InteractionResponse {
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter!("twilight_interaction_errors_total");
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%err, "couldn't handle interaction");
+
+        InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some((self.error_handler)(err)),
         }
     }
 
     #[cfg(any(feature = "gateway", feature = "webhook"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     async fn run_deferred(
         http: &Client,
+        application_id: Id<ApplicationMarker>,
         future: DeferredFuture,
         token: String,
     ) -> Result<(), Error> {
-        let callback = future.await;
+        #[cfg(feature = "tracing")]
+        let started = Instant::now();
 
-        let mut builder = http
-            .update_interaction_original(&token)?
-            .content(callback.content.as_deref())?
-            .embeds(Some(&callback.embeds))?;
+        let response = future.await;
 
-        if let Some(allowed_mentions) = callback.allowed_mentions {
-            builder = builder.allowed_mentions(allowed_mentions);
-        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(latency = ?started.elapsed(), "deferred response finished");
 
-        builder.exec().await?;
+        let result = update_original(
+            &http.interaction(application_id),
+            &token,
+            response.data,
+            &response.attachments,
+        )
+        .await;
 
-        Ok(())
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter!(
+            "twilight_interaction_deferred_total",
+            "outcome" => if result.is_ok() { "sent" } else { "failed" }
+        );
+
+        result
     }
 
     /// Handle an INTERACTION_CREATE event from the Discord Gateway, automatically sending the response over HTTP.
     ///
-    /// Requires the `gateway` feature to be enabled.
+    /// If the command is deferred, this waits for the deferred response to finish before returning,
+    /// so you'll usually want to spawn it on your executor of choice.
+    /// Commands declared with [`CommandDecl::slash_async`] are awaited directly, like with [`handle_async`](Self::handle_async).
+    ///
+    /// Requires the `gateway` feature to be enabled, and returns [`Error::MissingClient`]
+    /// if the handler was built without an HTTP client.
     #[cfg(feature = "gateway")]
     pub async fn handle_event(
         &self,
-        event: twilight_model::gateway::payload::InteractionCreate,
+        event: twilight_model::gateway::payload::incoming::InteractionCreate,
+    ) -> Result<(), Error> {
+        self.handle_event_with(event, Extensions::new()).await
+    }
+
+    /// Like [`handle_event`](Self::handle_event), but attaches `extensions` to the interaction,
+    /// which handlers can get through [`Context::extension`].
+    ///
+    /// Requires the `gateway` feature to be enabled.
+    #[cfg(feature = "gateway")]
+    pub async fn handle_event_with(
+        &self,
+        event: twilight_model::gateway::payload::incoming::InteractionCreate,
+        extensions: Extensions,
     ) -> Result<(), Error> {
-        let response = self.handle(event.0);
+        let http = self.http.as_deref().ok_or(Error::MissingClient)?;
+        let response = self.handle_async_with(event.0, extensions).await;
 
-        self.http
-            .interaction_callback(response.id, &response.token, &response.response)
+        http.interaction(response.application_id)
+            .create_response(response.id, &response.token, &response.response)
             .exec()
             .await?;
 
         if let Some(future) = response.future {
-            Self::run_deferred(&self.http, future, response.token).await?;
+            Self::run_deferred(http, response.application_id, future, response.token).await?;
         }
 
         Ok(())
     }
 
+    /// Handle any event from the Discord Gateway, ignoring everything other than INTERACTION_CREATE events,
+    /// which are handled the same as with [`handle_event`](Self::handle_event).
+    ///
+    /// This saves having to match on the event first when passing every event straight to the handler.
+    ///
+    /// Requires the `gateway` feature to be enabled.
+    #[cfg(feature = "gateway")]
+    pub async fn handle_gateway_event(&self, event: twilight_gateway::Event) -> Result<(), Error> {
+        match event {
+            twilight_gateway::Event::InteractionCreate(event) => self.handle_event(event).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle an interaction sent to an outgoing webhook, returning the HTTP response to send back to Discord.
+    ///
+    /// If the command is deferred, a [`DeferredResponse`] which sends the deferred response is also returned.
+    /// It isn't tied to any particular runtime, so it's up to the caller to spawn it onto their executor.
+    /// Since nothing is waiting on it, make sure to do something with its result, like with
+    /// [`DeferredResponse::report_errors`]; otherwise, failing to send the response goes unnoticed.
+    ///
+    /// Requires the `webhook` feature to be enabled.
     #[cfg(feature = "webhook")]
     pub fn handle_request(
         &self,
         request: http::Request<&[u8]>,
         pub_key: &ed25519_dalek::PublicKey,
-    ) -> Result<
-        (
-            http::Response<Vec<u8>>,
-            Option<impl Future<Output = Result<(), Error>> + Send>,
-        ),
-        Error,
-    > {
+    ) -> Result<(http::Response<Vec<u8>>, Option<DeferredResponse>), Error> {
+        self.handle_request_with_buffer(request, pub_key, Vec::new())
+    }
+
+    /// Like [`handle_request`](Self::handle_request), but serializes the response into `buf` instead of a new `Vec`,
+    /// which saves allocating a new buffer for every request on busy servers.
+    ///
+    /// `buf` is cleared first, but keeps its capacity. It's returned as the body of the response,
+    /// so it can be taken back with [`http::Response::into_body`] once the response has been sent.
+    /// The length of the body is also set as the response's `Content-Length`.
+    ///
+    /// Requires the `webhook` feature to be enabled.
+    #[cfg(feature = "webhook")]
+    pub fn handle_request_with_buffer(
+        &self,
+        request: http::Request<&[u8]>,
+        pub_key: &ed25519_dalek::PublicKey,
+        mut buf: Vec<u8>,
+    ) -> Result<(http::Response<Vec<u8>>, Option<DeferredResponse>), Error> {
+        use http::header::CONTENT_LENGTH;
         use http::header::CONTENT_TYPE;
         use http::Response;
         use http::StatusCode;
 
+        buf.clear();
+
         let interaction = match process(request, pub_key) {
             Ok(interaction) => interaction,
             Err(status) => {
@@ -266,64 +1069,200 @@ impl Handler {
                     // This can never fail, so it's fine to `unwrap` it -
                     // `status` only fails if it fails to convert to a `StatusCode`, but it's already a `StatusCode`,
                     // and `body` never fails.
-                    Response::builder().status(status).body(vec![]).unwrap(),
+                    Response::builder().status(status).body(buf).unwrap(),
                     None,
                 ));
             }
         };
 
         let response = self.handle(interaction);
+        let id = response.id;
         let token = response.token;
+        let application_id = response.application_id;
 
-        let json = serde_json::to_vec(&response.response)?;
+        serde_json::to_writer(&mut buf, &response.response)?;
 
         Ok((
             Response::builder()
                 .status(StatusCode::OK)
                 .header(CONTENT_TYPE, "application/json")
-                .body(json)
+                .header(CONTENT_LENGTH, buf.len())
+                .body(buf)
                 // If this is going to fail, it will always fail.
                 .unwrap(),
             response.future.map(|future| {
                 let http = self.http.clone();
-                async move { Self::run_deferred(&http, future, token).await }
+                DeferredResponse {
+                    id,
+                    token: token.clone(),
+                    future: Box::pin(async move {
+                        let http = http.as_deref().ok_or(Error::MissingClient)?;
+                        Self::run_deferred(http, application_id, future, token).await
+                    }),
+                }
             }),
         ))
     }
 }
 
-/// Get the interaction sent in a request, or return an appropriate error code if it's invalid.
+/// A deferred response to an interaction received through a webhook, which still needs to be sent.
+///
+/// This is a future which sends the response once the command finishes,
+/// and also keeps track of which interaction it's for so that failures can be traced back to it.
+///
+/// Requires the `webhook` feature to be enabled.
 #[cfg(feature = "webhook")]
-fn process(
-    request: http::Request<&[u8]>,
-    pub_key: &ed25519_dalek::PublicKey,
-) -> Result<twilight_model::application::interaction::Interaction, http::StatusCode> {
-    use ed25519_dalek::Signature;
-    use ed25519_dalek::Verifier;
-    use hex::FromHex;
-    use http::Method;
-    use http::StatusCode;
+pub struct DeferredResponse {
+    id: Id<InteractionMarker>,
+    token: String,
+    future: Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>,
+}
 
-    // Check that the method used is a POST, all other methods are not allowed.
-    if request.method() != Method::POST {
-        return Err(StatusCode::METHOD_NOT_ALLOWED);
+#[cfg(feature = "webhook")]
+impl DeferredResponse {
+    /// The ID of the interaction this is a response to.
+    pub fn id(&self) -> Id<InteractionMarker> {
+        self.id
     }
 
-    // Extract the timestamp header for use later to check the signature.
-    let timestamp = request
-        .headers()
-        .get("x-signature-timestamp")
-        .ok_or(StatusCode::BAD_REQUEST)?;
-
-    // Extact the signature to check against.
-    let signature = request
-        .headers()
-        .get("x-signature-ed25519")
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    let signature =
-        Signature::new(FromHex::from_hex(signature).map_err(|_| StatusCode::BAD_REQUEST)?);
+    /// The token of the interaction this is a response to.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
 
-    let body = *request.body();
+    /// Sends the response, passing any error to `report` along with the interaction's ID,
+    /// for when the response is spawned onto an executor and nothing is waiting for its result.
+    ///
+    /// The ID is passed rather than the token, since the token lets anyone respond to the interaction
+    /// and so shouldn't end up in logs.
+    ///
+    /// ```no_run
+    /// # fn spawn(_: impl std::future::Future<Output = ()>) {}
+    /// # fn example(deferred: twilight_interaction::DeferredResponse) {
+    /// spawn(deferred.report_errors(|id, err| {
+    ///     eprintln!("failed to send deferred response to {}: {}", id, err)
+    /// }));
+    /// # }
+    /// ```
+    pub async fn report_errors<F: FnOnce(Id<InteractionMarker>, Error)>(self, report: F) {
+        let id = self.id;
+        if let Err(err) = self.await {
+            report(id, err);
+        }
+    }
+}
+
+#[cfg(feature = "webhook")]
+impl Future for DeferredResponse {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(feature = "webhook")]
+impl Debug for DeferredResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeferredResponse")
+            .field("id", &self.id)
+            .field("token", &format_args!("<redacted>"))
+            .field("future", &format_args!("<future>"))
+            .finish()
+    }
+}
+
+/// The default for [`HandlerBuilder::deferred_timeout`];
+/// a bit less than the 15 minutes Discord allows for editing the response, to leave time for the error message.
+#[cfg(feature = "tokio")]
+const DEFAULT_DEFERRED_TIMEOUT: Duration = Duration::from_secs(14 * 60);
+
+/// Wraps a deferred future so that it gives up and responds with `error_handler`'s message
+/// for [`HandleError::TimedOut`] if it takes longer than `timeout`.
+///
+/// When that happens, the original future is dropped, cancelling whatever it was doing.
+#[cfg(feature = "tokio")]
+fn with_timeout(
+    future: DeferredFuture,
+    timeout: Duration,
+    error_handler: ErrorHandlerFn,
+) -> DeferredFuture {
+    Box::pin(async move {
+        tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| error_handler(&HandleError::TimedOut { timeout }).into())
+    })
+}
+
+/// Get the interaction sent in a request, or return an appropriate error code if it's invalid.
+#[cfg(feature = "webhook")]
+fn process(
+    request: http::Request<&[u8]>,
+    pub_key: &ed25519_dalek::PublicKey,
+) -> Result<Interaction, http::StatusCode> {
+    use http::Method;
+    use http::StatusCode;
+
+    // Check that the method used is a POST, all other methods are not allowed.
+    if request.method() != Method::POST {
+        return Err(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    // Extract the timestamp header for use later to check the signature.
+    let timestamp = request
+        .headers()
+        .get("x-signature-timestamp")
+        .and_then(|timestamp| timestamp.to_str().ok())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    // Extact the signature to check against.
+    let signature = request
+        .headers()
+        .get("x-signature-ed25519")
+        .and_then(|signature| signature.to_str().ok())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    verify_interaction(request.body(), timestamp, signature, pub_key)
+}
+
+/// Checks that `body` was signed by Discord and parses the interaction it contains,
+/// for HTTP frameworks which don't use the `http` crate's `Request`, and so can't use [`Handler::handle_request`].
+///
+/// `timestamp` and `signature` are the values of the `X-Signature-Timestamp` and `X-Signature-Ed25519` headers,
+/// and `pub_key` is the application's public key. If anything's wrong, this returns the status code to respond with.
+///
+/// Requires the `webhook` feature to be enabled.
+///
+/// # Examples
+/// ```no_run
+/// use twilight_interaction::{verify_interaction, Handler};
+///
+/// # fn example(handler: Handler, pub_key: ed25519_dalek::PublicKey, body: &[u8], timestamp: &str, signature: &str) {
+/// match verify_interaction(body, timestamp, signature, &pub_key) {
+///     Ok(interaction) => {
+///         let response = handler.handle(interaction);
+///         // ...send `response` back...
+///     }
+///     Err(status) => {
+///         // ...respond with `status.as_u16()`...
+///     }
+/// }
+/// # }
+/// ```
+#[cfg(feature = "webhook")]
+pub fn verify_interaction(
+    body: &[u8],
+    timestamp: &str,
+    signature: &str,
+    pub_key: &ed25519_dalek::PublicKey,
+) -> Result<Interaction, http::StatusCode> {
+    use ed25519_dalek::Signature;
+    use ed25519_dalek::Verifier;
+    use hex::FromHex;
+    use http::StatusCode;
+
+    let signature =
+        Signature::from(<[u8; 64]>::from_hex(signature).map_err(|_| StatusCode::BAD_REQUEST)?);
 
     // Check if the signature matches and else return a error response.
     pub_key
@@ -334,41 +1273,222 @@ fn process(
     serde_json::from_slice::<Interaction>(body).map_err(|_| StatusCode::BAD_REQUEST)
 }
 
-pub struct HandlerBuilder {
-    global_commands: Vec<(&'static str, CommandDecl)>,
-    guild_commands: HashMap<GuildId, Vec<(&'static str, CommandDecl)>>,
-    component_handler: Option<
-        Box<
-            dyn Fn(Context, Message, MessageComponentInteractionData) -> ComponentResponse
-                + Send
-                + Sync,
-        >,
-    >,
-    http: Client,
+pub struct HandlerBuilder<S = ()> {
+    global_commands: Vec<(Cow<'static, str>, CommandDecl<S>)>,
+    guild_commands: HashMap<Id<GuildMarker>, Vec<(Cow<'static, str>, CommandDecl<S>)>>,
+    component_handlers: HashMap<&'static str, ComponentHandlerFn<S>>,
+    component_handler: Option<ComponentHandlerFn<S>>,
+    unknown_command_handler: Option<UnknownCommandHandlerFn<S>>,
+    autocomplete_handlers: HashMap<Cow<'static, str>, AutocompleteHandlerFn<S>>,
+    dynamic_choices: Vec<(
+        Cow<'static, str>,
+        Cow<'static, str>,
+        Box<dyn DynamicChoices<S>>,
+    )>,
+    builtin_ping: bool,
+    dev_guild: Option<Id<GuildMarker>>,
+    restrictions: HashMap<Cow<'static, str>, HashSet<Id<GuildMarker>>>,
+    cooldowns: HashMap<Cow<'static, str>, Duration>,
+    command_permissions:
+        HashMap<Id<GuildMarker>, HashMap<Cow<'static, str>, Vec<CommandPermissions>>>,
+    restricted_response: InteractionResponseData,
+    error_handler: ErrorHandlerFn,
+    allowed_mentions: Option<AllowedMentions>,
+    #[cfg(feature = "tokio")]
+    deferred_timeout: Duration,
+    http: Option<Arc<Client>>,
+    application_id: Option<Id<ApplicationMarker>>,
+    state: S,
 }
 
-impl HandlerBuilder {
-    pub fn global_command<T: Into<CommandDecl>>(mut self, name: &'static str, command: T) -> Self {
-        self.global_commands.push((name, command.into()));
+/// The name the built-in ping command is registered under.
+const PING_COMMAND_NAME: &str = "ping";
+
+/// Creates the built-in ping command, which reports how long it's been since `started`.
+fn ping_command<S>(started: Instant) -> CommandDecl<S> {
+    CommandDecl::Slash {
+        description: Cow::Borrowed("Checks that the bot is up and reports its uptime"),
+        options: vec![],
+        default_permission: true,
+        handler: Box::new(move |_, options, _| {
+            if let Some(option) = options.first() {
+                return Err(OptionError {
+                    name: option.name.clone(),
+                    reason: OptionErrorReason::Unknown,
+                }
+                .into());
+            }
+
+            let uptime = started.elapsed().as_secs();
+            let content = format!(
+                "Pong! Uptime: {}h {}m {}s",
+                uptime / 3600,
+                uptime / 60 % 60,
+                uptime % 60
+            );
+
+            Ok((
+                InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(InteractionResponseData {
+                        content: Some(content),
+                        flags: Some(MessageFlags::EPHEMERAL),
+                        ..EMPTY_CALLBACK
+                    }),
+                },
+                None,
+            ))
+        }),
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> HandlerBuilder<S> {
+    /// Adds a command which is registered globally.
+    ///
+    /// `name` can be either a `&'static str` or a `String`, so that names can be computed at runtime
+    /// (e.g. loaded from a localization file).
+    pub fn global_command<N: Into<Cow<'static, str>>, T: Into<CommandDecl<S>>>(
+        mut self,
+        name: N,
+        command: T,
+    ) -> Self {
+        self.global_commands.push((name.into(), command.into()));
         self
     }
 
-    pub fn guild_command<T: Into<CommandDecl>>(
+    /// Adds a command which is only registered in the guild `guild_id`.
+    pub fn guild_command<N: Into<Cow<'static, str>>, T: Into<CommandDecl<S>>>(
         mut self,
-        guild_id: GuildId,
-        name: &'static str,
+        guild_id: Id<GuildMarker>,
+        name: N,
         command: T,
     ) -> Self {
-        let guild_commands = self.guild_commands.entry(guild_id).or_insert_with(Vec::new);
-        guild_commands.push((name, command.into()));
+        let guild_commands = self.guild_commands.entry(guild_id).or_default();
+        guild_commands.push((name.into(), command.into()));
+        self
+    }
+
+    /// Adds a command which is only registered in the guild `guild_id`, and sets who can use it there to `permissions`.
+    ///
+    /// The permissions are set right after the command is registered, since that's when its ID is known.
+    /// This is the same as calling [`Handler::set_command_permissions`] after building the handler,
+    /// but keeps the permissions next to the command's declaration.
+    pub fn guild_command_with_permissions<N: Into<Cow<'static, str>>, T: Into<CommandDecl<S>>>(
+        mut self,
+        guild_id: Id<GuildMarker>,
+        name: N,
+        command: T,
+        permissions: Vec<CommandPermissions>,
+    ) -> Self {
+        let name = name.into();
+        self.command_permissions
+            .entry(guild_id)
+            .or_default()
+            .insert(name.clone(), permissions);
+        self.guild_command(guild_id, name, command)
+    }
+
+    /// Adds commands declared at runtime, such as ones loaded from a config file or a plugin registry.
+    ///
+    /// These are registered and dispatched exactly the same as commands added with
+    /// [`global_command`](Self::global_command) and [`guild_command`](Self::guild_command).
+    pub fn commands<I: IntoIterator<Item = CommandSpec<S>>>(mut self, commands: I) -> Self {
+        for spec in commands {
+            let entry = (Cow::Owned(spec.name), spec.command);
+            match spec.scope {
+                CommandScope::Global => self.global_commands.push(entry),
+                CommandScope::Guild(guild_id) => {
+                    self.guild_commands.entry(guild_id).or_default().push(entry)
+                }
+            }
+        }
+        self
+    }
+
+    /// Registers every command declared with the command macros globally.
+    ///
+    /// Commands are collected from the whole program, but only ones whose [`Context`] has the state type `S` are included,
+    /// so handlers with different types of state can each auto-register their own commands.
+    /// Commands which don't take a [`Context`] at all are only included when `S` is `()`;
+    /// with the `tracing` feature enabled, a warning is logged for each of them that's skipped.
+    ///
+    /// Requires the `auto-register` feature to be enabled.
+    #[cfg(feature = "auto-register")]
+    pub fn auto_register_global(self) -> Self {
+        self.auto_register_module(CommandScope::Global, "")
+    }
+
+    /// Registers every command declared with the command macros in the guild `guild_id`.
+    ///
+    /// See [`auto_register_global`](Self::auto_register_global) for which commands are included.
+    ///
+    /// Requires the `auto-register` feature to be enabled.
+    #[cfg(feature = "auto-register")]
+    pub fn auto_register_guild(self, guild_id: Id<GuildMarker>) -> Self {
+        self.auto_register_module(CommandScope::Guild(guild_id), "")
+    }
+
+    /// Registers every command declared with the command macros in the module `module` (such as `my_bot::commands::admin`)
+    /// or any of its submodules, in `scope`.
+    ///
+    /// This allows grouping commands into modules which are registered differently,
+    /// for example keeping admin commands in a separate guild. An empty `module` includes every command.
+    ///
+    /// See [`auto_register_global`](Self::auto_register_global) for which commands are included.
+    ///
+    /// Requires the `auto-register` feature to be enabled.
+    #[cfg(feature = "auto-register")]
+    pub fn auto_register_module(self, scope: CommandScope, module: &str) -> Self {
+        let commands = inventory::iter::<crate::AutoCommand>
+            .into_iter()
+            .filter(|command| command.is_in(module))
+            .filter_map(
+                |command| match (command.describe)().downcast::<CommandDecl<S>>() {
+                    Ok(decl) => Some(CommandSpec {
+                        name: command.name.to_string(),
+                        scope,
+                        command: *decl,
+                    }),
+                    // Commands using a different type of state belong to some other handler,
+                    // but ones without a `Context` could have been meant for this one.
+                    Err(_) => {
+                        #[cfg(feature = "tracing")]
+                        if command.stateless {
+                            tracing::warn!(
+                                name = command.name,
+                                "skipped auto-registering a command without a `Context`, \
+                             since those are only auto-registered with handlers without state"
+                            );
+                        }
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        self.commands(commands)
+    }
+
+    /// Registers a handler for message components with the custom ID `custom_id`.
+    ///
+    /// These take priority over the catch-all [`component_handler`](Self::component_handler).
+    ///
+    /// The token of the component interaction is available through [`Context::token`],
+    /// for sending follow-up messages.
+    pub fn on_component<
+        F: Fn(Context<S>, Message, ComponentData) -> ComponentResponse + Send + Sync + 'static,
+    >(
+        mut self,
+        custom_id: &'static str,
+        handler: F,
+    ) -> Self {
+        self.component_handlers.insert(custom_id, Box::new(handler));
         self
     }
 
+    /// Registers a handler for any message components which don't have a handler registered with [`on_component`](Self::on_component).
     pub fn component_handler<
-        F: Fn(Context, Message, MessageComponentInteractionData) -> ComponentResponse
-            + Send
-            + Sync
-            + 'static,
+        F: Fn(Context<S>, Message, ComponentData) -> ComponentResponse + Send + Sync + 'static,
     >(
         mut self,
         handler: F,
@@ -377,62 +1497,1465 @@ impl HandlerBuilder {
         self
     }
 
-    /// Registers the slash commands with Discord and returns the `Handler` to handle them.
-    pub async fn build(self) -> Result<Handler, Error> {
-        let mut command_handlers = Vec::new();
+    /// Registers a handler for commands which this handler doesn't know about,
+    /// such as ones registered manually rather than through this builder.
+    ///
+    /// Without one, those commands get the error response set by [`on_error`](Self::on_error).
+    pub fn unknown_command_handler<
+        R: CommandResponse,
+        F: Fn(Context<S>, CommandData) -> R + Send + Sync + 'static,
+    >(
+        mut self,
+        handler: F,
+    ) -> Self {
+        self.unknown_command_handler = Some(Box::new(move |context, data| {
+            handler(context, data).into_interaction_response()
+        }));
+        self
+    }
 
-        // TODO: do this in parallel with the guild commands.
-        let response = self
-            .http
-            .set_global_commands(
-                &self
-                    .global_commands
-                    .iter()
-                    .map(|(name, command)| command.description(name.to_string()))
-                    .collect::<Vec<_>>(),
-            )?
-            .exec()
-            .await?
-            .models()
-            .await?;
+    /// Registers a handler to provide autocomplete suggestions for the command named `name`.
+    ///
+    /// The handler is told which option the user is typing in, what they've typed so far,
+    /// and the other options they've already filled in, and returns the choices to suggest.
+    ///
+    /// Discord only asks for suggestions for options which have autocomplete turned on,
+    /// which is done with [`slash_command`](crate::slash_command)'s `autocomplete` argument.
+    pub fn autocomplete<
+        N: Into<Cow<'static, str>>,
+        F: Fn(Context<S>, AutocompleteInput) -> Vec<CommandOptionChoice> + Send + Sync + 'static,
+    >(
+        mut self,
+        name: N,
+        handler: F,
+    ) -> Self {
+        self.autocomplete_handlers
+            .insert(name.into(), Box::new(handler));
+        self
+    }
+
+    /// Fills in the choices of the command named `command`'s integer option `option` with `choices`
+    /// whenever the commands are registered.
+    ///
+    /// If the command is registered in several places, the choices are fetched separately for each of them,
+    /// so that they can depend on the guild.
+    pub fn dynamic_choices<
+        N: Into<Cow<'static, str>>,
+        O: Into<Cow<'static, str>>,
+        T: DynamicChoices<S> + 'static,
+    >(
+        mut self,
+        command: N,
+        option: O,
+        choices: T,
+    ) -> Self {
+        self.dynamic_choices
+            .push((command.into(), option.into(), Box::new(choices)));
+        self
+    }
+
+    /// Restricts the command(s) named `name` so that they can only be used in the guilds `allowed_guilds`.
+    ///
+    /// This is useful for commands which are registered globally, but should only work in certain guilds.
+    /// Using the command anywhere else gives the response set by [`restricted_response`](Self::restricted_response).
+    pub fn restrict<N: Into<Cow<'static, str>>, I: IntoIterator<Item = Id<GuildMarker>>>(
+        mut self,
+        name: N,
+        allowed_guilds: I,
+    ) -> Self {
+        self.restrictions
+            .entry(name.into())
+            .or_default()
+            .extend(allowed_guilds);
+        self
+    }
+
+    /// Makes each user wait `cooldown` between uses of the command(s) named `name`, to stop expensive commands being spammed.
+    ///
+    /// Using the command again too soon gives a [`HandleError::OnCooldown`] error,
+    /// whose response can be changed with [`on_error`](Self::on_error).
+    pub fn cooldown<N: Into<Cow<'static, str>>>(mut self, name: N, cooldown: Duration) -> Self {
+        self.cooldowns.insert(name.into(), cooldown);
+        self
+    }
+
+    /// Sets the response given when a command restricted with [`restrict`](Self::restrict) is used outside of its allowed guilds.
+    ///
+    /// Defaults to an ephemeral message saying that the command isn't available.
+    pub fn restricted_response(mut self, response: impl IntoCallbackData) -> Self {
+        self.restricted_response = response.into_callback_data();
+        self
+    }
+
+    /// Sets the response given when an interaction can't be handled, such as an unknown command or an invalid option.
+    ///
+    /// Defaults to an ephemeral message describing the error. The returned message is sent exactly as-is,
+    /// so leaving out [`MessageFlags::EPHEMERAL`] makes error messages visible to everyone:
+    ///
+    /// ```no_run
+    /// use twilight_interaction::Handler;
+    ///
+    /// let http = twilight_http::Client::new("my_token".to_string());
+    ///
+    /// let builder = Handler::builder(http).on_error(|err| format!("Something went wrong: {}", err));
+    /// ```
+    pub fn on_error<R: IntoCallbackData, F: Fn(&HandleError) -> R + Send + Sync + 'static>(
+        mut self,
+        handler: F,
+    ) -> Self {
+        self.error_handler = Arc::new(move |err| handler(err).into_callback_data());
+        self
+    }
+
+    /// Sets the allowed mentions used by responses which don't set their own,
+    /// including the messages sent once deferred responses finish.
+    ///
+    /// This stops responses from accidentally pinging people, for example when they repeat text a user typed.
+    /// Other client settings, like proxies and timeouts, can be configured by building the `Client` passed to
+    /// [`Handler::builder`] with `Client::builder`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use twilight_interaction::Handler;
+    /// use twilight_model::channel::message::AllowedMentions;
+    ///
+    /// let http = twilight_http::Client::new("my_token".to_string());
+    ///
+    /// // Don't ping anyone unless a response explicitly allows it.
+    /// let builder = Handler::builder(http).allowed_mentions(AllowedMentions::default());
+    /// ```
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.allowed_mentions = Some(allowed_mentions);
+        self
+    }
+
+    /// Sets how long deferred responses can take before they're cancelled,
+    /// and the loading message is replaced with the [error handler's](Self::on_error) message for [`HandleError::TimedOut`] instead.
+    ///
+    /// Defaults to 14 minutes, just under the 15 minutes Discord allows for editing the response.
+    ///
+    /// Requires the `tokio` feature to be enabled.
+    #[cfg(feature = "tokio")]
+    pub fn deferred_timeout(mut self, timeout: Duration) -> Self {
+        self.deferred_timeout = timeout;
+        self
+    }
+
+    /// Sets the application ID of the bot, which is needed to register commands.
+    ///
+    /// If this isn't set, registering commands fails with [`Error::MissingApplicationId`].
+    /// It has no effect on handlers without an HTTP client.
+    pub fn application_id(mut self, application_id: Id<ApplicationMarker>) -> Self {
+        self.application_id = Some(application_id);
+        self
+    }
+
+    /// Registers a built-in global `/ping` command, which replies ephemerally with how long the handler has been running.
+    ///
+    /// If a global command named `ping` has already been declared, that command takes precedence and the built-in one isn't registered.
+    pub fn with_builtin_ping(mut self) -> Self {
+        self.builtin_ping = true;
+        self
+    }
 
-        for (command, description) in self
+    /// Registers all the global commands in the guild `guild_id` instead, if it's `Some`.
+    ///
+    /// Guild commands update instantly, whereas global commands can take up to an hour to propagate,
+    /// so this is useful for testing changes to global commands in a development guild.
+    /// The bot's actual global commands are left untouched while this is set.
+    ///
+    /// This takes an `Option` so that it can be easily driven by an environment variable,
+    /// with the same code registering commands globally in production:
+    ///
+    /// ```no_run
+    /// use twilight_interaction::Handler;
+    /// use twilight_model::id::Id;
+    ///
+    /// # async {
+    /// let http = twilight_http::Client::new("my_token".to_string());
+    ///
+    /// let dev_guild = std::env::var("DEV_GUILD_ID")
+    ///     .ok()
+    ///     .map(|id| id.parse::<Id<_>>().expect("invalid DEV_GUILD_ID"));
+    ///
+    /// let handler = Handler::builder(http)
+    ///     // ...
+    ///     .dev_guild(dev_guild)
+    ///     .build()
+    ///     .await
+    ///     .unwrap();
+    /// # };
+    /// ```
+    pub fn dev_guild(mut self, guild_id: Option<Id<GuildMarker>>) -> Self {
+        self.dev_guild = guild_id;
+        self
+    }
+
+    /// Where the global commands are actually registered, taking [`dev_guild`](Self::dev_guild) into account.
+    fn global_scope(&self) -> CommandScope {
+        match self.dev_guild {
+            Some(guild_id) => CommandScope::Guild(guild_id),
+            None => CommandScope::Global,
+        }
+    }
+
+    /// Whether the built-in ping command should be registered,
+    /// which it isn't if there's already a global slash command with the same name.
+    fn includes_builtin_ping(&self) -> bool {
+        self.builtin_ping
+            && !self.global_commands.iter().any(|(name, command)| {
+                *name == PING_COMMAND_NAME && command.kind() == CommandType::ChatInput
+            })
+    }
+
+    /// The scope, type and name of every command, including the built-in ping command if it's registered.
+    fn command_keys(&self) -> impl Iterator<Item = (CommandScope, CommandType, &str)> {
+        let global_scope = self.global_scope();
+        let global_keys = self
             .global_commands
-            .into_iter()
-            .map(|(_, command)| command)
-            .zip(response.into_iter())
-        {
-            command_handlers.push((description.id.unwrap(), command.into()))
+            .iter()
+            .map(move |(name, command)| (global_scope, command.kind(), name.as_ref()));
+        let guild_keys = self
+            .guild_commands
+            .iter()
+            .flat_map(|(&guild_id, commands)| {
+                commands.iter().map(move |(name, command)| {
+                    (CommandScope::Guild(guild_id), command.kind(), name.as_ref())
+                })
+            });
+        let ping_key = if self.includes_builtin_ping() {
+            Some((global_scope, CommandType::ChatInput, PING_COMMAND_NAME))
+        } else {
+            None
+        };
+
+        global_keys.chain(guild_keys).chain(ping_key)
+    }
+
+    /// Returns [`Error::DuplicateCommand`] if two commands of the same type registered in the same place
+    /// have the same name, which Discord would otherwise reject.
+    ///
+    /// Commands of different types can share a name, like a slash command and a user command both called `info`.
+    fn check_duplicates(&self) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+        for key in self.command_keys() {
+            if !seen.insert(key) {
+                let (_, _, name) = key;
+                return Err(Error::DuplicateCommand {
+                    name: name.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The descriptions of all the commands, grouped by where they're registered,
+    /// since in dev guild mode the global commands end up alongside that guild's commands.
+    fn grouped_commands(&self) -> HashMap<CommandScope, Vec<Command>> {
+        let mut commands: HashMap<CommandScope, Vec<Command>> = HashMap::new();
+
+        let global_commands = commands.entry(self.global_scope()).or_default();
+        global_commands.extend(
+            self.global_commands
+                .iter()
+                .map(|(name, command)| command.description(name.to_string())),
+        );
+        if self.includes_builtin_ping() {
+            global_commands
+                .push(ping_command::<S>(Instant::now()).description(PING_COMMAND_NAME.to_string()));
         }
 
-        for (guild_id, commands) in self.guild_commands.into_iter() {
-            let response = self
-                .http
-                .set_guild_commands(
-                    guild_id,
-                    &commands
+        for (&guild_id, guild_commands) in &self.guild_commands {
+            commands
+                .entry(CommandScope::Guild(guild_id))
+                .or_default()
+                .extend(
+                    guild_commands
                         .iter()
-                        .map(|(name, command)| command.description(name.to_string()))
-                        .collect::<Vec<_>>(),
-                )?
+                        .map(|(name, command)| command.description(name.to_string())),
+                );
+        }
+
+        commands
+    }
+
+    /// Fetches the choices added with [`dynamic_choices`](Self::dynamic_choices) and fills them into `commands`.
+    async fn fill_dynamic_choices(
+        &self,
+        commands: &mut HashMap<CommandScope, Vec<Command>>,
+    ) -> Result<(), Error> {
+        for (&scope, commands) in commands.iter_mut() {
+            let context = Context {
+                http: self.http.clone(),
+                application_id: self.application_id,
+                state: self.state.clone(),
+                guild_id: match scope {
+                    CommandScope::Global => None,
+                    CommandScope::Guild(guild_id) => Some(guild_id),
+                },
+                user_id: None,
+                token: String::new(),
+                locale: String::new(),
+                extensions: Extensions::new(),
+            };
+
+            for (name, option, choices) in &self.dynamic_choices {
+                // Only slash commands have options, and there might be a user or message command with the same name.
+                let command = match commands
+                    .iter_mut()
+                    .find(|command| command.kind == CommandType::ChatInput && command.name == *name)
+                {
+                    Some(command) => command,
+                    // The command isn't registered in this scope.
+                    None => continue,
+                };
+                let choices = choices.choices(&context).await;
+                set_choices(name, &mut command.options, option, choices)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the permissions passed to [`guild_command_with_permissions`](Self::guild_command_with_permissions)
+    /// for the command named `name` in `scope`, if there are any.
+    async fn apply_permissions(
+        &self,
+        http: &InteractionClient<'_>,
+        scope: CommandScope,
+        name: &str,
+        id: Id<CommandMarker>,
+    ) -> Result<(), Error> {
+        let guild_id = match scope {
+            CommandScope::Global => return Ok(()),
+            CommandScope::Guild(guild_id) => guild_id,
+        };
+
+        let permissions = self
+            .command_permissions
+            .get(&guild_id)
+            .and_then(|permissions| permissions.get(name));
+        if let Some(permissions) = permissions {
+            http.update_command_permissions(guild_id, id, permissions)?
                 .exec()
-                .await?
-                .models()
                 .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers the commands with Discord, without building the `Handler`.
+    ///
+    /// The returned IDs can be passed to [`build_with_ids`](Self::build_with_ids),
+    /// possibly in a different process if commands are registered in a separate deploy step.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self), err))]
+    pub async fn register(&self) -> Result<CommandIds, Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+        self.check_duplicates()?;
+        let mut ids = CommandIds::new();
+
+        let mut grouped_commands = self.grouped_commands();
+        self.fill_dynamic_choices(&mut grouped_commands).await?;
+
+        // TODO: do these in parallel.
+        for (scope, commands) in grouped_commands {
+            let response = match scope {
+                CommandScope::Global => {
+                    http.set_global_commands(&commands)
+                        .exec()
+                        .await?
+                        .models()
+                        .await?
+                }
+                CommandScope::Guild(guild_id) => {
+                    http.set_guild_commands(guild_id, &commands)
+                        .exec()
+                        .await?
+                        .models()
+                        .await?
+                }
+            };
+            ids.insert_registered(scope, &commands, response)?;
 
-            for (command, description) in commands
-                .into_iter()
-                .map(|(_, command)| command)
-                .zip(response.into_iter())
-            {
-                command_handlers.push((description.id.unwrap(), command.into()))
+            for command in &commands {
+                if let Some(id) = ids.get(scope, command.kind, &command.name) {
+                    self.apply_permissions(&http, scope, &command.name, id)
+                        .await?;
+                }
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(?scope, count = commands.len(), "registered commands");
+        }
+
+        Ok(ids)
+    }
+
+    /// Builds the `Handler` without registering any commands with Discord,
+    /// using `ids` to find out which command each interaction is for.
+    ///
+    /// Returns [`Error::MissingCommandId`] if any of the declared commands aren't in `ids`,
+    /// or [`Error::DuplicateCommand`] if two commands of the same type registered in the same place have the same name.
+    pub fn build_with_ids(mut self, ids: &CommandIds) -> Result<Handler<S>, Error> {
+        self.check_duplicates()?;
+
+        if self.includes_builtin_ping() {
+            self.global_commands.push((
+                Cow::Borrowed(PING_COMMAND_NAME),
+                ping_command(Instant::now()),
+            ));
+        }
+
+        let mut command_handlers = Vec::new();
+        let mut restrictions = HashMap::new();
+        let mut cooldowns = HashMap::new();
+
+        let global_scope = self.global_scope();
+        let global_commands = self
+            .global_commands
+            .into_iter()
+            .map(|command| (global_scope, command));
+        let guild_commands = self
+            .guild_commands
+            .into_iter()
+            .flat_map(|(guild_id, commands)| {
+                commands
+                    .into_iter()
+                    .map(move |command| (CommandScope::Guild(guild_id), command))
+            });
+
+        for (scope, (name, command)) in global_commands.chain(guild_commands) {
+            let id =
+                ids.get(scope, command.kind(), &name)
+                    .ok_or_else(|| Error::MissingCommandId {
+                        name: name.to_string(),
+                    })?;
+            if let Some(allowed_guilds) = self.restrictions.get(&name) {
+                restrictions.insert(id, allowed_guilds.clone());
             }
+            if let Some(&cooldown) = self.cooldowns.get(&name) {
+                cooldowns.insert(id, cooldown);
+            }
+            command_handlers.push((id, scope, name, command.into()))
         }
 
         Ok(Handler {
             http: self.http,
+            application_id: self.application_id,
+            state: self.state,
             command_handlers,
+            component_handlers: self.component_handlers,
             component_handler: self.component_handler,
+            unknown_command_handler: self.unknown_command_handler,
+            autocomplete_handlers: self.autocomplete_handlers,
+            restrictions,
+            restricted_response: self.restricted_response,
+            cooldowns,
+            last_used: Mutex::new(LastUsed::default()),
+            error_handler: self.error_handler,
+            allowed_mentions: self.allowed_mentions,
+            #[cfg(feature = "tokio")]
+            deferred_timeout: self.deferred_timeout,
         })
     }
+
+    /// Builds the `Handler` without contacting Discord at all, giving each command a made-up ID.
+    ///
+    /// This is intended for testing: the returned IDs can be used to construct interactions to pass to
+    /// [`Handler::handle`], and the resulting [`Response`]s can be inspected without sending anything.
+    /// The `Client` passed to [`Handler::builder`] is still given to commands through their `Context`,
+    /// but it's never used by `handle` itself, so it doesn't need a valid token.
+    ///
+    /// # Panics
+    /// Panics if two commands of the same type registered in the same place have the same name.
+    pub fn build_offline(self) -> (Handler<S>, CommandIds) {
+        let mut ids = CommandIds::new();
+        for ((scope, kind, name), next_id) in self.command_keys().zip(1..) {
+            ids.insert(scope, kind, name.to_string(), Id::new(next_id));
+        }
+
+        let handler = self
+            .build_with_ids(&ids)
+            // Every command has just been given an ID, so this can only fail if some of them have the same name.
+            .expect("duplicate command names");
+
+        (handler, ids)
+    }
+
+    /// Registers the commands with Discord one at a time, returning the `Handler` along with whether each one succeeded.
+    ///
+    /// Unlike [`build`](Self::build), which fails as soon as Discord rejects any of the commands,
+    /// this tells you exactly which ones were rejected and why.
+    /// The returned `Handler` only handles the commands which were registered successfully.
+    ///
+    /// Since the commands aren't all set at once, commands which were previously registered
+    /// but have since been removed from the builder are left in place, rather than being deleted like with `build`.
+    ///
+    /// This still returns an error without registering anything if the handler has no HTTP client or application ID,
+    /// or if two commands of the same type registered in the same place have the same name.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub async fn try_build(mut self) -> Result<(Handler<S>, Vec<Registration>), Error> {
+        let http = command_client(self.http.as_deref(), self.application_id)?;
+        self.check_duplicates()?;
+
+        let mut ids = CommandIds::new();
+        let mut results = Vec::new();
+
+        let mut grouped_commands = self.grouped_commands();
+        self.fill_dynamic_choices(&mut grouped_commands).await?;
+
+        for (scope, commands) in grouped_commands {
+            for command in commands {
+                let mut result =
+                    create_command(&http, scope, &command)
+                        .await
+                        .and_then(|registered| {
+                            registered.id.ok_or_else(|| Error::MissingCommandId {
+                                name: command.name.clone(),
+                            })
+                        });
+
+                // Leave out commands whose permissions couldn't be set, rather than letting anyone use them.
+                if let Ok(id) = result {
+                    if let Err(err) = self
+                        .apply_permissions(&http, scope, &command.name, id)
+                        .await
+                    {
+                        result = Err(err);
+                    }
+                }
+
+                if let Ok(id) = &result {
+                    ids.insert(scope, command.kind, command.name.clone(), *id);
+                }
+                #[cfg(feature = "tracing")]
+                if let Err(err) = &result {
+                    tracing::warn!(?scope, name = %command.name, %err, "failed to register command");
+                }
+                results.push(Registration {
+                    scope,
+                    name: command.name,
+                    result,
+                });
+            }
+        }
+
+        // Leave out the commands which failed to register, since they can't be used anyway.
+        let global_scope = self.global_scope();
+        self.global_commands
+            .retain(|(name, command)| ids.get(global_scope, command.kind(), name).is_some());
+        for (&guild_id, commands) in &mut self.guild_commands {
+            let scope = CommandScope::Guild(guild_id);
+            commands.retain(|(name, command)| ids.get(scope, command.kind(), name).is_some());
+        }
+        if ids
+            .get(global_scope, CommandType::ChatInput, PING_COMMAND_NAME)
+            .is_none()
+        {
+            self.builtin_ping = false;
+        }
+
+        let handler = self.build_with_ids(&ids)?;
+        Ok((handler, results))
+    }
+
+    /// Registers the commands with Discord and returns the `Handler` to handle them.
+    ///
+    /// This is the same as calling [`register`](Self::register) followed by [`build_with_ids`](Self::build_with_ids).
+    pub async fn build(self) -> Result<Handler<S>, Error> {
+        let ids = self.register().await?;
+        self.build_with_ids(&ids)
+    }
+}
+
+/// The outcome of registering one command with [`HandlerBuilder::try_build`].
+#[derive(Debug)]
+pub struct Registration {
+    pub scope: CommandScope,
+    pub name: String,
+    /// The ID Discord assigned to the command, or why it couldn't be registered.
+    pub result: Result<Id<CommandMarker>, Error>,
+}
+
+/// Counts `interaction` by its kind, and commands by their name.
+#[cfg(feature = "metrics")]
+fn record_interaction(interaction: &Interaction) {
+    let kind = match interaction {
+        Interaction::Ping(_) => "ping",
+        Interaction::ApplicationCommand(command) => {
+            metrics::increment_counter!(
+                "twilight_interaction_commands_total",
+                "command" => command.data.name.clone()
+            );
+            "command"
+        }
+        Interaction::ApplicationCommandAutocomplete(_) => "autocomplete",
+        Interaction::MessageComponent(_) => "component",
+        _ => "other",
+    };
+    metrics::increment_counter!("twilight_interaction_interactions_total", "kind" => kind);
+}
+
+/// The ID of the user who triggered an interaction, which is in `member` in guilds and `user` in DMs.
+fn author_id(member: Option<&PartialMember>, user: Option<&User>) -> Option<Id<UserMarker>> {
+    member
+        .and_then(|member| member.user.as_ref())
+        .or(user)
+        .map(|user| user.id)
+}
+
+/// Gets the client to manage commands and responses with, which needs to know the bot's application ID.
+pub(crate) fn command_client(
+    http: Option<&Client>,
+    application_id: Option<Id<ApplicationMarker>>,
+) -> Result<InteractionClient<'_>, Error> {
+    let http = http.ok_or(Error::MissingClient)?;
+    let application_id = application_id.ok_or(Error::MissingApplicationId)?;
+    Ok(http.interaction(application_id))
+}
+
+/// Registers a single command in `scope`, replacing any existing command with the same name.
+async fn create_command(
+    http: &InteractionClient<'_>,
+    scope: CommandScope,
+    command: &Command,
+) -> Result<Command, Error> {
+    let name = command.name.as_str();
+    let description = command.description.as_str();
+    let options = command.options.as_slice();
+    // Discord defaults to letting everyone use commands, so this is the same as leaving it unset.
+    let default_permission = command.default_permission.unwrap_or(true);
+
+    let registered = match (scope, command.kind) {
+        (CommandScope::Global, CommandType::Message) => {
+            http.create_global_command()
+                .message(name)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+        (CommandScope::Global, CommandType::User) => {
+            http.create_global_command()
+                .user(name)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+        (CommandScope::Global, _) => {
+            http.create_global_command()
+                .chat_input(name, description)?
+                .command_options(options)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+        (CommandScope::Guild(guild_id), CommandType::Message) => {
+            http.create_guild_command(guild_id)
+                .message(name)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+        (CommandScope::Guild(guild_id), CommandType::User) => {
+            http.create_guild_command(guild_id)
+                .user(name)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+        (CommandScope::Guild(guild_id), _) => {
+            http.create_guild_command(guild_id)
+                .chat_input(name, description)?
+                .command_options(options)?
+                .default_permission(default_permission)
+                .exec()
+                .await?
+                .model()
+                .await?
+        }
+    };
+
+    Ok(registered)
+}
+
+/// The IDs Discord assigned to registered commands.
+///
+/// These are returned by [`HandlerBuilder::register`], but can also be constructed manually
+/// to build a `Handler` for commands which were registered elsewhere.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CommandIds {
+    ids: HashMap<(CommandScope, CommandType, String), Id<CommandMarker>>,
+}
+
+impl CommandIds {
+    /// Creates an empty set of command IDs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the command of type `kind` named `name` in `scope` has the ID `id`.
+    ///
+    /// The type is part of the key since commands of different types can have the same name.
+    pub fn insert(
+        &mut self,
+        scope: CommandScope,
+        kind: CommandType,
+        name: impl Into<String>,
+        id: Id<CommandMarker>,
+    ) {
+        self.ids.insert((scope, kind, name.into()), id);
+    }
+
+    /// Gets the ID of the command of type `kind` named `name` in `scope`.
+    pub fn get(
+        &self,
+        scope: CommandScope,
+        kind: CommandType,
+        name: &str,
+    ) -> Option<Id<CommandMarker>> {
+        // `HashMap` can't look up a tuple key by borrowed parts, so this has to allocate.
+        self.ids.get(&(scope, kind, name.to_string())).copied()
+    }
+
+    /// Records the IDs from Discord's response to registering `commands`.
+    fn insert_registered(
+        &mut self,
+        scope: CommandScope,
+        commands: &[Command],
+        response: Vec<Command>,
+    ) -> Result<(), Error> {
+        for (command, registered) in commands.iter().zip(response) {
+            let id = registered.id.ok_or_else(|| Error::MissingCommandId {
+                name: command.name.clone(),
+            })?;
+            self.insert(scope, command.kind, command.name.clone(), id);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use twilight_model::application::command::Command;
+    use twilight_model::application::command::CommandType;
+    use twilight_model::application::component::ComponentType;
+    use twilight_model::application::interaction::application_command::ApplicationCommand;
+    use twilight_model::application::interaction::application_command::CommandData;
+    use twilight_model::application::interaction::application_command::CommandDataOption;
+    use twilight_model::application::interaction::application_command::CommandInteractionDataResolved;
+    use twilight_model::application::interaction::application_command::CommandOptionValue;
+    use twilight_model::application::interaction::message_component::MessageComponentInteraction;
+    use twilight_model::application::interaction::message_component::MessageComponentInteractionData;
+    use twilight_model::application::interaction::Interaction;
+    use twilight_model::application::interaction::InteractionType;
+    use twilight_model::channel::message::AllowedMentions;
+    use twilight_model::channel::message::MessageFlags;
+    use twilight_model::channel::message::MessageType;
+    use twilight_model::channel::Message;
+    use twilight_model::datetime::Timestamp;
+    use twilight_model::http::interaction::InteractionResponse;
+    use twilight_model::http::interaction::InteractionResponseData;
+    use twilight_model::http::interaction::InteractionResponseType;
+    use twilight_model::id::marker::CommandMarker;
+    use twilight_model::id::Id;
+    use twilight_model::user::User;
+
+    use super::CommandHandler;
+    use super::CommandIds;
+    use super::Handler;
+    use super::HandlerBuilder;
+    use super::MIN_PRUNE_AT;
+    use crate::defer;
+    use crate::CommandDecl;
+    use crate::CommandScope;
+    use crate::ComponentResponse;
+    use crate::Context;
+    use crate::Error;
+    use crate::ErrorHandlerFn;
+    use crate::Extensions;
+    use crate::HandleError;
+    use crate::IntoCallbackData;
+    use crate::OptionError;
+    use crate::OptionErrorReason;
+    use crate::Response;
+    use crate::EMPTY_CALLBACK;
+
+    fn slash() -> CommandDecl {
+        CommandDecl::slash("A command", vec![], |_, _, _| {
+            Ok((
+                InteractionResponse {
+                    kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                    data: None,
+                },
+                None,
+            ))
+        })
+    }
+
+    fn user() -> CommandDecl {
+        CommandDecl::User {
+            default_permission: true,
+            handler: Box::new(|_, _| {
+                (
+                    InteractionResponse {
+                        kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                        data: None,
+                    },
+                    None,
+                )
+            }),
+        }
+    }
+
+    fn builder() -> HandlerBuilder {
+        Handler::builder_without_client(())
+    }
+
+    #[test]
+    fn duplicate_global_commands() {
+        let result = builder()
+            .global_command("info", slash())
+            .global_command("info", slash())
+            .build_with_ids(&Default::default());
+        assert!(matches!(result, Err(Error::DuplicateCommand { name }) if name == "info"));
+    }
+
+    #[test]
+    fn duplicate_guild_commands() {
+        let result = builder()
+            .guild_command(Id::new(1), "info", slash())
+            .guild_command(Id::new(1), "info", slash())
+            .build_with_ids(&Default::default());
+        assert!(matches!(result, Err(Error::DuplicateCommand { name }) if name == "info"));
+    }
+
+    #[test]
+    fn same_name_in_different_places() {
+        let (_, ids) = builder()
+            .global_command("info", slash())
+            .guild_command(Id::new(1), "info", slash())
+            .guild_command(Id::new(2), "info", slash())
+            .build_offline();
+        assert!(ids
+            .get(CommandScope::Global, CommandType::ChatInput, "info")
+            .is_some());
+        assert!(ids
+            .get(
+                CommandScope::Guild(Id::new(2)),
+                CommandType::ChatInput,
+                "info"
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn same_name_with_different_types() {
+        let (handler, ids) = builder()
+            .global_command("info", slash())
+            .global_command("info", user())
+            .guild_command(Id::new(1), "info", slash())
+            .guild_command(Id::new(1), "info", user())
+            .build_offline();
+
+        let slash_id = ids.get(CommandScope::Global, CommandType::ChatInput, "info");
+        let user_id = ids.get(CommandScope::Global, CommandType::User, "info");
+        assert!(slash_id.is_some() && user_id.is_some());
+        assert_ne!(slash_id, user_id);
+        assert_eq!(handler.commands().count(), 4);
+    }
+
+    #[test]
+    fn command_ids_in_guild() {
+        let (handler, ids) = builder()
+            .global_command("info", slash())
+            .guild_command(Id::new(1), "info", user())
+            .guild_command(Id::new(2), "info", slash())
+            .build_offline();
+
+        let mut expected = vec![
+            ids.get(CommandScope::Global, CommandType::ChatInput, "info")
+                .unwrap(),
+            ids.get(CommandScope::Guild(Id::new(1)), CommandType::User, "info")
+                .unwrap(),
+        ];
+        let mut actual = handler.command_ids_in(Id::new(1), "info");
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+        assert!(handler.command_ids_in(Id::new(1), "other").is_empty());
+    }
+
+    fn context() -> Context {
+        Context {
+            http: None,
+            application_id: None,
+            state: (),
+            guild_id: None,
+            user_id: None,
+            token: String::new(),
+            locale: String::new(),
+            extensions: Extensions::new(),
+        }
+    }
+
+    fn reply(content: String) -> InteractionResponse {
+        InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                content: Some(content),
+                ..EMPTY_CALLBACK
+            }),
+        }
+    }
+
+    fn user_with_id(id: u64) -> User {
+        User {
+            accent_color: None,
+            avatar: None,
+            banner: None,
+            bot: false,
+            discriminator: 1,
+            email: None,
+            flags: None,
+            id: Id::new(id),
+            locale: None,
+            mfa_enabled: None,
+            name: format!("user {}", id),
+            premium_type: None,
+            public_flags: None,
+            system: None,
+            verified: None,
+        }
+    }
+
+    fn message_with_id(id: u64) -> Message {
+        Message {
+            activity: None,
+            application: None,
+            application_id: None,
+            attachments: vec![],
+            author: user_with_id(1),
+            channel_id: Id::new(1),
+            components: vec![],
+            content: format!("message {}", id),
+            edited_timestamp: None,
+            embeds: vec![],
+            flags: None,
+            guild_id: None,
+            id: Id::new(id),
+            interaction: None,
+            kind: MessageType::Regular,
+            member: None,
+            mention_channels: vec![],
+            mention_everyone: false,
+            mention_roles: vec![],
+            mentions: vec![],
+            pinned: false,
+            reactions: vec![],
+            reference: None,
+            referenced_message: None,
+            sticker_items: vec![],
+            timestamp: Timestamp::from_secs(0).unwrap(),
+            thread: None,
+            tts: false,
+            webhook_id: None,
+        }
+    }
+
+    fn empty_resolved() -> CommandInteractionDataResolved {
+        CommandInteractionDataResolved {
+            attachments: HashMap::new(),
+            channels: HashMap::new(),
+            members: HashMap::new(),
+            messages: HashMap::new(),
+            roles: HashMap::new(),
+            users: HashMap::new(),
+        }
+    }
+
+    /// Runs a context menu command `handler` targeting `target_id`, with `resolved` as its resolved data.
+    fn run_context_menu(
+        handler: CommandHandler<()>,
+        kind: CommandType,
+        target_id: u64,
+        resolved: CommandInteractionDataResolved,
+    ) -> Result<InteractionResponse, HandleError> {
+        let data = CommandData {
+            id: Id::new(1),
+            name: "target".to_string(),
+            kind,
+            options: vec![],
+            resolved: Some(resolved),
+            target_id: Some(Id::new(target_id)),
+        };
+        let error_handler: ErrorHandlerFn = Arc::new(|_| EMPTY_CALLBACK);
+        handler
+            .handle(context(), data, &error_handler)
+            .map(|(response, _)| response)
+    }
+
+    #[test]
+    #[should_panic(expected = "Discord only allows 1 to 100")]
+    fn empty_description_override() {
+        slash().with_description("");
+    }
+
+    #[test]
+    #[should_panic(expected = "Discord only allows 1 to 100")]
+    fn long_description_override() {
+        slash().with_description("a".repeat(101));
+    }
+
+    #[test]
+    fn default_permission() {
+        let description = |command: CommandDecl| command.description("info".to_string());
+        assert_eq!(description(slash()).default_permission, Some(true));
+        assert_eq!(
+            description(slash().with_default_permission(false)).default_permission,
+            Some(false)
+        );
+        assert_eq!(
+            description(user().with_default_permission(false)).default_permission,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn registered_command_without_id() {
+        let commands = vec![Command {
+            application_id: None,
+            default_permission: None,
+            description: "A command".to_string(),
+            guild_id: None,
+            id: None,
+            kind: CommandType::ChatInput,
+            name: "info".to_string(),
+            options: vec![],
+            version: Id::new(1),
+        }];
+
+        let mut ids = CommandIds::new();
+        let result = ids.insert_registered(CommandScope::Global, &commands, commands.clone());
+        assert!(matches!(result, Err(Error::MissingCommandId { name }) if name == "info"));
+        assert_eq!(ids, CommandIds::new());
+    }
+
+    #[test]
+    fn message_command_picks_target() {
+        let handler =
+            CommandHandler::Message(Box::new(|_, message| (reply(message.content), None)));
+        let resolved = CommandInteractionDataResolved {
+            messages: (1..=3)
+                .map(|id| (Id::new(id), message_with_id(id)))
+                .collect(),
+            ..empty_resolved()
+        };
+
+        let response = run_context_menu(handler, CommandType::Message, 2, resolved);
+        assert_eq!(response, Ok(reply("message 2".to_string())));
+    }
+
+    #[test]
+    fn user_command_picks_target() {
+        let handler = CommandHandler::User(Box::new(|_, user| (reply(user.name), None)));
+        let resolved = CommandInteractionDataResolved {
+            users: (1..=3).map(|id| (Id::new(id), user_with_id(id))).collect(),
+            ..empty_resolved()
+        };
+
+        let response = run_context_menu(handler, CommandType::User, 3, resolved);
+        assert_eq!(response, Ok(reply("user 3".to_string())));
+    }
+
+    #[test]
+    fn user_command_missing_target() {
+        let handler = CommandHandler::User(Box::new(|_, user| (reply(user.name), None)));
+        let resolved = CommandInteractionDataResolved {
+            users: (1..=2).map(|id| (Id::new(id), user_with_id(id))).collect(),
+            ..empty_resolved()
+        };
+
+        let response = run_context_menu(handler, CommandType::User, 3, resolved);
+        assert_eq!(response, Err(HandleError::InvalidUserCommand));
+    }
+
+    #[test]
+    fn async_slash_non_message_response() {
+        let handler = CommandHandler::AsyncSlash(
+            Box::new(|_, _, _| {
+                Box::pin(async {
+                    Ok((
+                        InteractionResponse {
+                            kind: InteractionResponseType::UpdateMessage,
+                            data: None,
+                        },
+                        None,
+                    ))
+                })
+            }),
+            true,
+        );
+        let data = CommandData {
+            id: Id::new(1),
+            name: "update".to_string(),
+            kind: CommandType::ChatInput,
+            options: vec![],
+            resolved: None,
+            target_id: None,
+        };
+        let error_handler: ErrorHandlerFn = Arc::new(|err| InteractionResponseData {
+            content: Some(err.to_string()),
+            ..EMPTY_CALLBACK
+        });
+
+        let (response, future) = handler.handle(context(), data, &error_handler).unwrap();
+        assert_eq!(
+            response.data.and_then(|data| data.flags),
+            Some(MessageFlags::EPHEMERAL)
+        );
+
+        let deferred = futures::executor::block_on(future.unwrap());
+        assert_eq!(
+            deferred.data.content.as_deref(),
+            Some("Error: the command '/update' didn't respond with a message")
+        );
+    }
+
+    /// An interaction using the slash command `id`, with `options`, sent by the user `user_id` if there is one.
+    fn slash_interaction(
+        id: Id<CommandMarker>,
+        user_id: Option<u64>,
+        options: Vec<CommandDataOption>,
+    ) -> Interaction {
+        Interaction::ApplicationCommand(Box::new(ApplicationCommand {
+            application_id: Id::new(1),
+            channel_id: Id::new(1),
+            data: CommandData {
+                id,
+                name: "slow".to_string(),
+                kind: CommandType::ChatInput,
+                options,
+                resolved: None,
+                target_id: None,
+            },
+            guild_id: None,
+            guild_locale: None,
+            id: Id::new(1),
+            kind: InteractionType::ApplicationCommand,
+            locale: "en-US".to_string(),
+            member: None,
+            token: String::new(),
+            user: user_id.map(user_with_id),
+        }))
+    }
+
+    /// A handler with a command called `slow` with a cooldown of `cooldown`, along with the command's ID.
+    ///
+    /// The command fails to parse its options if it's given any.
+    fn cooldown_handler(cooldown: Duration) -> (Handler, Id<CommandMarker>) {
+        let command = CommandDecl::slash("A slow command", vec![], |_, options, _| {
+            match options.first() {
+                Some(option) => Err(OptionError {
+                    name: option.name.clone(),
+                    reason: OptionErrorReason::Unknown,
+                }
+                .into()),
+                None => Ok((reply("done".to_string()), None)),
+            }
+        });
+        let (handler, ids) = builder()
+            .global_command("slow", command)
+            .cooldown("slow", cooldown)
+            .on_error(|err| match err {
+                HandleError::OnCooldown { .. } => "on cooldown",
+                _ => "invalid",
+            })
+            .build_offline();
+        let id = ids
+            .get(CommandScope::Global, CommandType::ChatInput, "slow")
+            .unwrap();
+        (handler, id)
+    }
+
+    /// The content of the message `handler` responds to `interaction` with.
+    fn content(handler: &Handler, interaction: Interaction) -> String {
+        handler
+            .handle(interaction)
+            .into_response()
+            .data
+            .and_then(|data| data.content)
+            .unwrap()
+    }
+
+    #[test]
+    fn cooldown_rejects_within_window() {
+        let (handler, id) = cooldown_handler(Duration::from_secs(60));
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "done"
+        );
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "on cooldown"
+        );
+    }
+
+    #[test]
+    fn cooldown_allows_after_window() {
+        let (handler, id) = cooldown_handler(Duration::from_millis(10));
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "done"
+        );
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "done"
+        );
+    }
+
+    #[test]
+    fn cooldown_is_per_user() {
+        let (handler, id) = cooldown_handler(Duration::from_secs(60));
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "done"
+        );
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(2), vec![])),
+            "done"
+        );
+    }
+
+    #[test]
+    fn cooldown_without_user() {
+        let (handler, id) = cooldown_handler(Duration::from_secs(60));
+        assert_eq!(
+            content(&handler, slash_interaction(id, None, vec![])),
+            "done"
+        );
+        assert_eq!(
+            content(&handler, slash_interaction(id, None, vec![])),
+            "done"
+        );
+    }
+
+    #[test]
+    fn cooldown_ignores_invalid_options() {
+        let (handler, id) = cooldown_handler(Duration::from_secs(60));
+        let option = CommandDataOption {
+            focused: false,
+            name: "extra".to_string(),
+            value: CommandOptionValue::Boolean(true),
+        };
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![option])),
+            "invalid"
+        );
+        assert_eq!(
+            content(&handler, slash_interaction(id, Some(1), vec![])),
+            "done"
+        );
+    }
+
+    #[test]
+    fn cooldown_forgets_expired_uses() {
+        let (handler, id) = cooldown_handler(Duration::ZERO);
+        for user_id in 1..=MIN_PRUNE_AT as u64 {
+            content(&handler, slash_interaction(id, Some(user_id), vec![]));
+        }
+        assert!(handler.last_used.lock().unwrap().uses.is_empty());
+    }
+
+    /// The response `handler` gives to a click of the button `custom_id`.
+    fn click(handler: &Handler, custom_id: &str) -> Response {
+        handler.handle(Interaction::MessageComponent(Box::new(
+            MessageComponentInteraction {
+                application_id: Id::new(1),
+                channel_id: Id::new(1),
+                data: MessageComponentInteractionData {
+                    custom_id: custom_id.to_string(),
+                    component_type: ComponentType::Button,
+                    values: vec![],
+                },
+                guild_id: None,
+                guild_locale: None,
+                id: Id::new(1),
+                kind: InteractionType::MessageComponent,
+                locale: "en-US".to_string(),
+                member: None,
+                message: message_with_id(1),
+                token: String::new(),
+                user: Some(user_with_id(1)),
+            },
+        )))
+    }
+
+    /// A message with `flags` set.
+    fn with_flags(flags: MessageFlags) -> InteractionResponseData {
+        InteractionResponseData {
+            content: Some("hi".to_string()),
+            flags: Some(flags),
+            ..Default::default()
+        }
+    }
+
+    /// The flags of the message `handler` responds to a click of the button `custom_id` with.
+    fn flags(handler: &Handler, custom_id: &str) -> Option<MessageFlags> {
+        click(handler, custom_id)
+            .into_response()
+            .data
+            .and_then(|data| data.flags)
+    }
+
+    #[test]
+    fn update_strips_ephemeral() {
+        let (handler, _) = builder()
+            .on_component("both", |_, _, _| {
+                ComponentResponse::Update(with_flags(
+                    MessageFlags::EPHEMERAL | MessageFlags::SUPPRESS_EMBEDS,
+                ))
+            })
+            .on_component("ephemeral", |_, _, _| {
+                ComponentResponse::Update(with_flags(MessageFlags::EPHEMERAL))
+            })
+            .build_offline();
+        assert_eq!(flags(&handler, "both"), Some(MessageFlags::SUPPRESS_EMBEDS));
+        assert_eq!(flags(&handler, "ephemeral"), None);
+    }
+
+    #[test]
+    fn ephemeral_message_keeps_flags() {
+        let (handler, _) = builder()
+            .on_component("suppressed", |_, _, _| {
+                ComponentResponse::EphemeralMessage(with_flags(MessageFlags::SUPPRESS_EMBEDS))
+            })
+            .on_component("plain", |_, _, _| {
+                ComponentResponse::EphemeralMessage("hi".into_callback_data())
+            })
+            .build_offline();
+        assert_eq!(
+            flags(&handler, "suppressed"),
+            Some(MessageFlags::EPHEMERAL | MessageFlags::SUPPRESS_EMBEDS)
+        );
+        assert_eq!(flags(&handler, "plain"), Some(MessageFlags::EPHEMERAL));
+    }
+
+    #[tokio::test]
+    async fn default_allowed_mentions() {
+        let explicit = AllowedMentions {
+            replied_user: true,
+            ..Default::default()
+        };
+        let (handler, _) = builder()
+            .allowed_mentions(AllowedMentions::default())
+            .on_component("default", |_, _, _| {
+                ComponentResponse::Message("hi".into_callback_data())
+            })
+            .on_component("explicit", {
+                let explicit = explicit.clone();
+                move |_, _, _| {
+                    ComponentResponse::Message(InteractionResponseData {
+                        allowed_mentions: Some(explicit.clone()),
+                        ..Default::default()
+                    })
+                }
+            })
+            .on_component("deferred", |_, _, _| ComponentResponse::DeferredMessage {
+                future: defer(async { "hi" }),
+                ephemeral: false,
+            })
+            .build_offline();
+
+        let allowed_mentions = |custom_id| {
+            click(&handler, custom_id)
+                .into_response()
+                .data
+                .and_then(|data| data.allowed_mentions)
+        };
+        assert_eq!(
+            allowed_mentions("default"),
+            Some(AllowedMentions::default())
+        );
+        assert_eq!(allowed_mentions("explicit"), Some(explicit));
+
+        let future = click(&handler, "deferred").take_future().unwrap();
+        assert_eq!(
+            future.await.data.allowed_mentions,
+            Some(AllowedMentions::default())
+        );
+    }
+
+    #[test]
+    fn restricted_commands() {
+        let (handler, ids) = builder()
+            .global_command("secret", slash())
+            .global_command("public", slash())
+            .restrict("secret", [Id::new(1)])
+            .restricted_response("restricted")
+            .build_offline();
+        let secret = ids
+            .get(CommandScope::Global, CommandType::ChatInput, "secret")
+            .unwrap();
+        let public = ids
+            .get(CommandScope::Global, CommandType::ChatInput, "public")
+            .unwrap();
+
+        assert!(!handler.is_restricted(secret, Some(Id::new(1))));
+        assert!(handler.is_restricted(secret, Some(Id::new(2))));
+        assert!(handler.is_restricted(secret, None));
+        assert!(!handler.is_restricted(public, Some(Id::new(2))));
+        assert!(!handler.is_restricted(public, None));
+
+        let respond = |guild_id: Option<u64>| {
+            let mut interaction = slash_interaction(secret, Some(1), vec![]);
+            if let Interaction::ApplicationCommand(command) = &mut interaction {
+                command.guild_id = guild_id.map(Id::new);
+            }
+            handler.handle(interaction).into_response()
+        };
+        assert_eq!(
+            respond(Some(1)).kind,
+            InteractionResponseType::DeferredChannelMessageWithSource
+        );
+        for response in [respond(Some(2)), respond(None)] {
+            assert_eq!(
+                response.data.and_then(|data| data.content).as_deref(),
+                Some("restricted")
+            );
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn deferred_timeout() {
+        let (handler, _) = builder()
+            .deferred_timeout(Duration::from_millis(10))
+            .on_error(|err| match err {
+                HandleError::TimedOut { .. } => "timed out",
+                _ => "other error",
+            })
+            .on_component("slow", |_, _, _| {
+                ComponentResponse::DeferredUpdate(defer(futures::future::pending::<&str>()))
+            })
+            .build_offline();
+
+        let future = click(&handler, "slow").take_future().unwrap();
+        assert_eq!(future.await.data.content.as_deref(), Some("timed out"));
+    }
 }
@@ -1,114 +1,548 @@
+//! A library for handling Discord interactions with twilight.
+//!
+//! # Async runtimes
+//!
+//! This crate doesn't depend on any particular async runtime.
+//! Deferred responses are either awaited inline ([`Handler::handle_event`])
+//! or handed back to the caller to run however they like ([`Handler::handle_request`]),
+//! so they can be spawned on tokio, async-std, smol or any other executor.
+//! Features which do need a specific runtime are gated behind a feature flag named after it.
+//!
+//! Currently the only such feature is `tokio`, which enables [`HandlerBuilder::deferred_timeout`].
+//!
+//! # Auto-registration
+//!
+//! Enabling the `auto-register` feature collects every command declared with
+//! [`slash_command`], [`message_command`] or [`user_command`] across the whole program,
+//! so that they can all be registered at once with [`HandlerBuilder::auto_register_global`],
+//! [`HandlerBuilder::auto_register_guild`] or [`HandlerBuilder::auto_register_module`],
+//! instead of listing them all out by name.
+//!
+//! # Tracing
+//!
+//! Enabling the `tracing` feature instruments handling interactions, running deferred responses
+//! and registering commands with [`tracing`](https://docs.rs/tracing) spans and events,
+//! including which command was used, the interaction's ID and how long handling it took.
+//!
+//! # Metrics
+//!
+//! Enabling the `metrics` feature records counters with the [`metrics`](https://docs.rs/metrics) crate:
+//! - `twilight_interaction_interactions_total`, labelled with the `kind` of interaction.
+//! - `twilight_interaction_commands_total`, labelled with the name of the `command` used.
+//! - `twilight_interaction_deferred_total`, labelled with whether the deferred response was `sent` or `failed`.
+//! - `twilight_interaction_errors_total`, for interactions which got an error response.
+
+// `Error` wraps twilight's HTTP errors, which are big, but errors are rare enough that boxing them isn't worth it,
+// and the handler's lookup tables are clearer spelled out than hidden behind type aliases.
+#![allow(clippy::result_large_err, clippy::type_complexity)]
+
+use std::borrow::Cow;
+use std::fmt;
 use std::fmt::Debug;
+use std::fmt::Formatter;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
 use thiserror::Error;
-use twilight_http::request::application::interaction::update_original_response::UpdateOriginalResponseError;
-use twilight_http::request::application::InteractionError;
 use twilight_http::response::DeserializeBodyError;
-use twilight_model::application::callback::CallbackData;
-use twilight_model::application::callback::InteractionResponse;
 use twilight_model::application::command::Command;
 use twilight_model::application::command::CommandOption;
 use twilight_model::application::command::CommandType;
+use twilight_model::application::interaction::application_command::CommandData;
 use twilight_model::application::interaction::application_command::CommandDataOption;
 use twilight_model::application::interaction::application_command::CommandInteractionDataResolved;
+use twilight_model::application::interaction::InteractionType;
 use twilight_model::channel::Message;
-use twilight_model::id::InteractionId;
+use twilight_model::http::interaction::InteractionResponse;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::http::interaction::InteractionResponseType;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::marker::InteractionMarker;
+use twilight_model::id::Id;
 use twilight_model::user::User;
+use twilight_validate::command::CommandValidationError;
+use twilight_validate::command::DESCRIPTION_LENGTH_MAX;
+use twilight_validate::command::DESCRIPTION_LENGTH_MIN;
+use twilight_validate::message::MessageValidationError;
 
+pub use twilight_interaction_macros::message_command;
+/// Option names are checked against Discord's rules when the command is declared, including ones set with `rename`,
+/// so a name which is empty, longer than 32 characters or contains any characters besides lowercase letters,
+/// digits, dashes and underscores fails to compile:
+///
+/// ```compile_fail
+/// use twilight_interaction::slash_command;
+///
+/// #[slash_command(description("Looks up a user", id = "The user's ID"), rename(id = "User ID"))]
+/// fn look_up(id: i64) -> String {
+///     id.to_string()
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// use twilight_interaction::slash_command;
+///
+/// #[slash_command(description("Looks up a user", id = "The user's ID"), rename(id = ""))]
+/// fn look_up(id: i64) -> String {
+///     id.to_string()
+/// }
+/// # fn main() {}
+/// ```
+///
+/// The numbers added to the names of array elements count towards the limit too:
+///
+/// ```compile_fail
+/// use twilight_interaction::slash_command;
+///
+/// #[slash_command(
+///     description("Looks up users", ids = "A user's ID"),
+///     rename(ids = "the-ids-of-the-users-to-look-up")
+/// )]
+/// fn look_up(ids: [Option<i64>; 2]) -> String {
+///     format!("{:?}", ids)
+/// }
+/// # fn main() {}
+/// ```
 pub use twilight_interaction_macros::slash_command;
+pub use twilight_interaction_macros::user_command;
 // Only show the trait in docs, not the derive macro.
 #[doc(hidden)]
 pub use twilight_interaction_macros::Choices;
 
+mod attachment;
+mod auto_register;
+mod autocomplete;
+mod callback_builder;
+mod component;
+mod components_builder;
 mod context;
+mod dynamic_choices;
+mod editor;
+mod extensions;
 mod handler;
 mod option_types;
 
+pub use attachment::*;
+pub use auto_register::*;
+pub use autocomplete::*;
+
+pub use callback_builder::*;
+pub use component::*;
+pub use components_builder::*;
 pub use context::*;
+pub use dynamic_choices::*;
+pub use editor::*;
+pub use extensions::*;
 pub use handler::*;
+#[cfg(feature = "auto-register")]
+#[doc(hidden)]
+pub use inventory;
 pub use option_types::*;
 
-/// An empty `CallbackData`, to use for the pointless field of `InteractionResponse::DeferredChannelMessageWithSource`.
-const EMPTY_CALLBACK: CallbackData = CallbackData {
+/// An empty `InteractionResponseData`, to fill in with the fields a response actually sets.
+const EMPTY_CALLBACK: InteractionResponseData = InteractionResponseData {
     allowed_mentions: None,
+    attachments: None,
+    choices: None,
     components: None,
     content: None,
-    embeds: vec![],
+    custom_id: None,
+    embeds: None,
     flags: None,
+    title: None,
     tts: None,
 };
 
+/// A response to a message component interaction.
 pub enum ComponentResponse {
-    Message(CallbackData),
-    DeferredMessage(DeferredFuture),
-    Update(CallbackData),
+    /// Respond with a new message, leaving the message the component is attached to as it is.
+    ///
+    /// This is visible to everyone unless `data` has the ephemeral flag set; see [`EphemeralMessage`](Self::EphemeralMessage).
+    Message(InteractionResponseData),
+    /// Respond with a new message that's only visible to the user who used the component,
+    /// leaving the message the component is attached to as it is.
+    ///
+    /// This is the same as [`Message`](Self::Message) with the ephemeral flag added,
+    /// which is useful for privately acknowledging an action, like a button that logs something.
+    EphemeralMessage(InteractionResponseData),
+    /// Show a loading state, then respond with a new message once `future` completes.
+    DeferredMessage {
+        future: DeferredFuture,
+        /// Whether the loading state, and so the eventual message, is only visible to the user who triggered the interaction.
+        ephemeral: bool,
+    },
+    /// Update the message the component is attached to.
+    ///
+    /// The content, embeds, components and allowed mentions are all updated, and `tts` has no effect on edits.
+    /// Whether the message is ephemeral can't be changed, so the `EPHEMERAL` flag is removed from `flags`;
+    /// other flags, like `SUPPRESS_EMBEDS`, are kept.
+    Update(InteractionResponseData),
+    /// Acknowledge the interaction, then update the message the component is attached to once the future completes.
+    ///
+    /// Since this edits an existing message, it can't be made ephemeral.
     DeferredUpdate(DeferredFuture),
 }
 
+impl Debug for ComponentResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(data) => f.debug_tuple("Message").field(data).finish(),
+            Self::EphemeralMessage(data) => f.debug_tuple("EphemeralMessage").field(data).finish(),
+            Self::DeferredMessage { ephemeral, .. } => f
+                .debug_struct("DeferredMessage")
+                .field("future", &format_args!("<future>"))
+                .field("ephemeral", ephemeral)
+                .finish(),
+            Self::Update(data) => f.debug_tuple("Update").field(data).finish(),
+            Self::DeferredUpdate(_) => f
+                .debug_tuple("DeferredUpdate")
+                .field(&format_args!("<future>"))
+                .finish(),
+        }
+    }
+}
+
 /// A future for the result of an asynchronous command.
-pub type DeferredFuture = Pin<Box<dyn Future<Output = CallbackData> + Send>>;
+///
+/// Plain messages can be converted into its output with `.into()`.
+pub type DeferredFuture = Pin<Box<dyn Future<Output = WithAttachments> + Send>>;
+
+/// Turns any future whose output can be converted into a message into a [`DeferredFuture`],
+/// so that it doesn't have to be boxed and converted by hand.
+///
+/// # Examples
+/// ```
+/// use twilight_interaction::{defer, ComponentResponse};
+///
+/// let response = ComponentResponse::DeferredMessage {
+///     future: defer(async { "Done!" }),
+///     ephemeral: true,
+/// };
+/// ```
+pub fn defer<F>(future: F) -> DeferredFuture
+where
+    F: Future + Send + 'static,
+    F::Output: Into<WithAttachments>,
+{
+    Box::pin(async move { future.await.into() })
+}
 
+/// The result of handling an interaction with [`Handler::handle`].
+///
+/// [`Handler::handle_event`] and [`Handler::handle_request`] take care of sending this to Discord,
+/// but it can also be sent manually, for example when using a different HTTP framework.
 pub struct Response {
     /// The actual `InteractionResponse` to return to discord.
     response: InteractionResponse,
     /// If the response is deferred, a future to await to get the deferred message.
     future: Option<DeferredFuture>,
     /// The interaction ID extracted from the interaction.
-    id: InteractionId,
+    id: Id<InteractionMarker>,
+    /// The ID of the application the interaction was sent to, used to update the response.
+    application_id: Id<ApplicationMarker>,
     /// The interaction token extracted from the interaction.
     token: String,
 }
 
+impl Debug for Response {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("response", &self.response)
+            .field(
+                "future",
+                &self.future.as_ref().map(|_| format_args!("<future>")),
+            )
+            .field("id", &self.id)
+            .field("application_id", &self.application_id)
+            // The token lets anyone respond to the interaction, so keep it out of logs.
+            .field("token", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl Response {
+    /// The ID of the interaction being responded to.
+    pub fn id(&self) -> Id<InteractionMarker> {
+        self.id
+    }
+
+    /// The ID of the application the interaction was sent to, which is needed to update the response.
+    pub fn application_id(&self) -> Id<ApplicationMarker> {
+        self.application_id
+    }
+
+    /// The token of the interaction being responded to, used to send the response and any follow-ups.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The response to send to Discord.
+    pub fn response(&self) -> &InteractionResponse {
+        &self.response
+    }
+
+    /// Whether this is the response to a `Ping` interaction, which Discord sends to check a webhook endpoint works.
+    ///
+    /// [`Handler::handle_request`] deals with these already, but other HTTP frameworks may need to special-case them.
+    /// Pings can only be received over webhooks, so this is always `false` when using the gateway.
+    pub fn is_pong(&self) -> bool {
+        self.response.kind == InteractionResponseType::Pong
+    }
+
+    /// Consumes this `Response`, returning the `InteractionResponse` to send to Discord.
+    ///
+    /// Make sure to call [`take_future`](Self::take_future) first if the response might be deferred.
+    pub fn into_response(self) -> InteractionResponse {
+        self.response
+    }
+
+    /// Takes the future for the deferred message, if the response is deferred.
+    ///
+    /// Once it completes, the original response should be updated with its output.
+    pub fn take_future(&mut self) -> Option<DeferredFuture> {
+        self.future.take()
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error(transparent)]
-    Interaction(#[from] InteractionError),
+    CommandValidation(#[from] CommandValidationError),
     #[error(transparent)]
     Http(#[from] twilight_http::Error),
     #[error(transparent)]
     Deserialize(#[from] DeserializeBodyError),
     #[error(transparent)]
-    UpdateResponse(#[from] UpdateOriginalResponseError),
+    MessageValidation(#[from] MessageValidationError),
     #[cfg(feature = "webhook")]
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    /// Something which needs to make an HTTP request was used with a handler built without an HTTP client.
+    #[error("This handler was built without an HTTP client")]
+    MissingClient,
+    /// Commands were registered or updated without setting the bot's application ID
+    /// with [`HandlerBuilder::application_id`].
+    #[error("The application ID needs to be set to manage commands")]
+    MissingApplicationId,
+    /// Discord's response to registering commands didn't include the ID of one of them.
+    #[error("Discord didn't return an ID for the command '{name}'")]
+    MissingCommandId { name: String },
+    /// Two commands of the same type registered in the same place (globally, or in the same guild) have the same name.
+    #[error("There's more than one command named '{name}'")]
+    DuplicateCommand { name: String },
+    /// There's no command with the given name.
+    #[error("There's no command named '{name}'")]
+    UnknownCommand { name: String },
+    /// Dynamic choices were added to an option which isn't one of the command's integer options.
+    #[error("The command '{command}' has no integer option named '{option}'")]
+    UnknownChoicesOption { command: String, option: String },
+    /// A [`DynamicChoices`] implementation returned more choices than Discord allows for one option.
+    #[error("The option '{option}' of the command '{command}' was given {count} choices, but the most allowed is 25")]
+    TooManyChoices {
+        command: String,
+        option: String,
+        count: usize,
+    },
+}
+
+/// Something wrong with an interaction which stopped it from being handled normally.
+///
+/// These are reported to the user with the response set by [`HandlerBuilder::on_error`],
+/// which gets passed the error so that it can pick a response based on exactly what went wrong.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum HandleError {
+    /// Some of the options of the slash command named `command` were missing or invalid.
+    #[error("{errors}")]
+    InvalidOption {
+        command: String,
+        errors: OptionErrors,
+    },
+    /// A message command didn't come with the message it was used on.
+    #[error("Invalid message command received")]
+    InvalidMessageCommand,
+    /// A user command didn't come with the user it was used on.
+    #[error("Invalid user command received")]
+    InvalidUserCommand,
+    /// The interaction was for a command this handler doesn't know about.
+    #[error("Unknown command '/{name}'")]
+    UnknownCommand { name: String },
+    /// The user used a command again before its [cooldown](HandlerBuilder::cooldown) ran out.
+    #[error("You're using this command too quickly; try again in {} seconds", .remaining.as_secs_f64().ceil())]
+    OnCooldown { remaining: Duration },
+    /// A message component was used without a handler registered for its custom ID, or a catch-all handler.
+    #[error("Error: no message component handler registered")]
+    UnknownComponent { custom_id: String },
+    /// The handler of the `async` slash command named `command` responded with something other than a message,
+    /// after [`Handler::handle`] had already deferred its response as a message.
+    #[error("Error: the command '/{command}' didn't respond with a message")]
+    UnsupportedResponse {
+        command: String,
+        kind: InteractionResponseType,
+    },
+    /// The interaction was of a kind this handler can't respond to, like a modal being submitted.
+    #[error("Error: this kind of interaction isn't supported")]
+    UnsupportedInteraction { kind: InteractionType },
+    /// A deferred response took longer than [`HandlerBuilder::deferred_timeout`] to complete, and was cancelled.
+    #[error("Error: this command took too long to respond")]
+    TimedOut { timeout: Duration },
 }
 
-pub(crate) type SlashHandlerFn = Box<
+pub(crate) type SlashHandlerFn<S> = Box<
     dyn Fn(
-            Context,
+            Context<S>,
             Vec<CommandDataOption>,
             Option<CommandInteractionDataResolved>,
-        ) -> Result<(InteractionResponse, Option<DeferredFuture>), String>
+        ) -> Result<(InteractionResponse, Option<DeferredFuture>), OptionErrors>
         + Send
         + Sync,
 >;
 
-pub(crate) type MessageHandlerFn =
-    Box<dyn Fn(Context, Message) -> (InteractionResponse, Option<DeferredFuture>) + Send + Sync>;
+pub(crate) type MessageHandlerFn<S> =
+    Box<dyn Fn(Context<S>, Message) -> (InteractionResponse, Option<DeferredFuture>) + Send + Sync>;
+
+pub(crate) type UserHandlerFn<S> =
+    Box<dyn Fn(Context<S>, User) -> (InteractionResponse, Option<DeferredFuture>) + Send + Sync>;
+
+pub(crate) type ComponentHandlerFn<S> =
+    Box<dyn Fn(Context<S>, Message, ComponentData) -> ComponentResponse + Send + Sync>;
+
+pub(crate) type UnknownCommandHandlerFn<S> = Box<
+    dyn Fn(Context<S>, CommandData) -> (InteractionResponse, Option<DeferredFuture>) + Send + Sync,
+>;
+
+pub(crate) type AsyncSlashHandlerFn<S> = Box<
+    dyn Fn(
+            Context<S>,
+            Vec<CommandDataOption>,
+            Option<CommandInteractionDataResolved>,
+        ) -> Pin<
+            Box<
+                dyn Future<
+                        Output = Result<
+                            (InteractionResponse, Option<DeferredFuture>),
+                            OptionErrors,
+                        >,
+                    > + Send,
+            >,
+        > + Send
+        + Sync,
+>;
 
-pub(crate) type UserHandlerFn =
-    Box<dyn Fn(Context, User) -> (InteractionResponse, Option<DeferredFuture>) + Send + Sync>;
+// This is an `Arc` so that deferred responses can report errors too.
+pub(crate) type ErrorHandlerFn = Arc<dyn Fn(&HandleError) -> InteractionResponseData + Send + Sync>;
 
-pub enum CommandDecl {
+/// A command, along with the function used to handle it.
+///
+/// `S` is the type of the state shared between commands, which is available through [`Context::state`].
+pub enum CommandDecl<S = ()> {
     Slash {
-        description: &'static str,
+        description: Cow<'static, str>,
         options: Vec<CommandOption>,
-        handler: SlashHandlerFn,
+        /// Whether everyone can use the command by default; see [`CommandDecl::with_default_permission`].
+        default_permission: bool,
+        handler: SlashHandlerFn<S>,
+    },
+    /// A slash command whose handler is `async`, and decides whether to respond immediately or defer once it's done.
+    ///
+    /// See [`CommandDecl::slash_async`] for more details.
+    AsyncSlash {
+        description: Cow<'static, str>,
+        options: Vec<CommandOption>,
+        /// Whether the response is ephemeral when [`Handler::handle`] has to defer it;
+        /// see [`CommandDecl::defer_ephemerally`].
+        ephemeral: bool,
+        /// Whether everyone can use the command by default; see [`CommandDecl::with_default_permission`].
+        default_permission: bool,
+        handler: AsyncSlashHandlerFn<S>,
     },
     Message {
-        handler: MessageHandlerFn,
+        /// Whether everyone can use the command by default; see [`CommandDecl::with_default_permission`].
+        default_permission: bool,
+        handler: MessageHandlerFn<S>,
     },
     User {
-        handler: UserHandlerFn,
+        /// Whether everyone can use the command by default; see [`CommandDecl::with_default_permission`].
+        default_permission: bool,
+        handler: UserHandlerFn<S>,
     },
 }
 
-impl<R: CommandResponse + 'static> From<fn(Context, Message) -> R> for CommandDecl {
-    fn from(func: fn(Context, Message) -> R) -> Self {
+impl<S> Debug for CommandDecl<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandDecl::Slash {
+                description,
+                options,
+                default_permission,
+                ..
+            } => f
+                .debug_struct("Slash")
+                .field("description", description)
+                .field("options", options)
+                .field("default_permission", default_permission)
+                .field("handler", &format_args!("<handler>"))
+                .finish(),
+            CommandDecl::AsyncSlash {
+                description,
+                options,
+                ephemeral,
+                default_permission,
+                ..
+            } => f
+                .debug_struct("AsyncSlash")
+                .field("description", description)
+                .field("options", options)
+                .field("ephemeral", ephemeral)
+                .field("default_permission", default_permission)
+                .field("handler", &format_args!("<handler>"))
+                .finish(),
+            CommandDecl::Message {
+                default_permission, ..
+            } => f
+                .debug_struct("Message")
+                .field("default_permission", default_permission)
+                .field("handler", &format_args!("<handler>"))
+                .finish(),
+            CommandDecl::User {
+                default_permission, ..
+            } => f
+                .debug_struct("User")
+                .field("default_permission", default_permission)
+                .field("handler", &format_args!("<handler>"))
+                .finish(),
+        }
+    }
+}
+
+/// Where a command is registered.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CommandScope {
+    /// The command is registered globally, and is available everywhere.
+    Global,
+    /// The command is only registered in one guild.
+    Guild(Id<GuildMarker>),
+}
+
+/// A command declared at runtime, for bots whose commands aren't known at compile time.
+///
+/// These can be registered with [`HandlerBuilder::commands`].
+#[derive(Debug)]
+pub struct CommandSpec<S = ()> {
+    pub name: String,
+    pub scope: CommandScope,
+    pub command: CommandDecl<S>,
+}
+
+impl<S: 'static, R: CommandResponse + 'static> From<fn(Context<S>, Message) -> R>
+    for CommandDecl<S>
+{
+    fn from(func: fn(Context<S>, Message) -> R) -> Self {
         CommandDecl::Message {
+            default_permission: true,
             handler: Box::new(move |context, message| {
                 func(context, message).into_interaction_response()
             }),
@@ -116,45 +550,212 @@ impl<R: CommandResponse + 'static> From<fn(Context, Message) -> R> for CommandDe
     }
 }
 
-impl<R: CommandResponse + 'static> From<fn(Context, User) -> R> for CommandDecl {
-    fn from(func: fn(Context, User) -> R) -> Self {
+impl<S: 'static, R: CommandResponse + 'static> From<fn(Context<S>, User) -> R> for CommandDecl<S> {
+    fn from(func: fn(Context<S>, User) -> R) -> Self {
         CommandDecl::User {
+            default_permission: true,
             handler: Box::new(move |context, user| func(context, user).into_interaction_response()),
         }
     }
 }
 
-impl CommandDecl {
+impl<S> CommandDecl<S> {
+    /// Declares a slash command from a handler closure, for commands which aren't known until runtime.
+    ///
+    /// `handler` is passed the options and resolved data Discord sent, and should return an `Err`
+    /// describing the offending options if any of them are invalid. A single [`OptionError`] can be returned with `?`.
+    pub fn slash<D, F>(description: D, options: Vec<CommandOption>, handler: F) -> Self
+    where
+        D: Into<Cow<'static, str>>,
+        F: Fn(
+                Context<S>,
+                Vec<CommandDataOption>,
+                Option<CommandInteractionDataResolved>,
+            ) -> Result<(InteractionResponse, Option<DeferredFuture>), OptionErrors>
+            + Send
+            + Sync
+            + 'static,
+    {
+        CommandDecl::Slash {
+            description: description.into(),
+            options,
+            default_permission: true,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Declares a slash command whose handler is `async`, for commands which need to do something asynchronous
+    /// (like looking something up in a database) to decide how to respond.
+    ///
+    /// Unlike `async` functions passed to [`slash_command`], which always defer their response,
+    /// the handler returns its response directly, so it can still respond immediately or defer only when it needs to.
+    ///
+    /// Handlers are only awaited directly by [`Handler::handle_async`] (and so [`Handler::handle_event`]),
+    /// in which case they need to finish within the 3 seconds Discord allows for responding.
+    /// [`Handler::handle`] can't wait for them, so it defers the response and then fills it in with whatever the handler returns.
+    /// Whether a message is ephemeral can't be changed after deferring it, so in that case it's decided by
+    /// [`defer_ephemerally`](Self::defer_ephemerally) rather than the handler's response.
+    pub fn slash_async<D, F, Fut, R>(
+        description: D,
+        options: Vec<CommandOption>,
+        handler: F,
+    ) -> Self
+    where
+        D: Into<Cow<'static, str>>,
+        F: Fn(Context<S>, Vec<CommandDataOption>, Option<CommandInteractionDataResolved>) -> Fut
+            + Send
+            + Sync
+            + 'static,
+        Fut: Future<Output = Result<R, OptionErrors>> + Send + 'static,
+        R: CommandResponse,
+    {
+        CommandDecl::AsyncSlash {
+            description: description.into(),
+            options,
+            ephemeral: false,
+            default_permission: true,
+            handler: Box::new(move |context, options, resolved| {
+                let future = handler(context, options, resolved);
+                let future: Pin<Box<dyn Future<Output = _> + Send>> =
+                    Box::pin(async move { Ok(future.await?.into_interaction_response()) });
+                future
+            }),
+        }
+    }
+
+    /// Replaces the description of a slash command, for when it's registered in several places
+    /// and should be described differently in some of them:
+    ///
+    /// ```no_run
+    /// use twilight_interaction::slash_command;
+    /// use twilight_interaction::Handler;
+    /// use twilight_model::id::Id;
+    ///
+    /// #[slash_command(description("Rolls a die"))]
+    /// fn roll() -> String {
+    ///     "4".to_string()
+    /// }
+    ///
+    /// # fn main() {
+    /// # let http = twilight_http::Client::new("my_token".to_string());
+    /// let builder = Handler::builder(http)
+    ///     .global_command(roll::NAME, roll::describe())
+    ///     .guild_command(
+    ///         Id::new(1234),
+    ///         roll::NAME,
+    ///         roll::describe().with_description("Rolls a die (beta)"),
+    ///     );
+    /// # }
+    /// ```
+    ///
+    /// Message and user commands don't have descriptions, so this has no effect on them.
+    ///
+    /// # Panics
+    /// Panics if `new_description` isn't between 1 and 100 characters long, since Discord would reject it,
+    /// just like [`slash_command`] rejects such descriptions at compile time.
+    pub fn with_description<D: Into<Cow<'static, str>>>(mut self, new_description: D) -> Self {
+        let new_description = new_description.into();
+        let len = new_description.chars().count();
+        assert!(
+            (DESCRIPTION_LENGTH_MIN..=DESCRIPTION_LENGTH_MAX).contains(&len),
+            "Description {:?} is {} characters long, but Discord only allows 1 to 100",
+            new_description,
+            len
+        );
+
+        if let CommandDecl::Slash { description, .. }
+        | CommandDecl::AsyncSlash { description, .. } = &mut self
+        {
+            *description = new_description;
+        }
+        self
+    }
+
+    /// Makes the response [`Handler::handle`] defers for a command declared with [`slash_async`](Self::slash_async) ephemeral,
+    /// so that only the user who used the command can see whatever the handler responds with.
+    ///
+    /// This has no effect on other kinds of commands.
+    pub fn defer_ephemerally(mut self) -> Self {
+        if let CommandDecl::AsyncSlash { ephemeral, .. } = &mut self {
+            *ephemeral = true;
+        }
+        self
+    }
+
+    /// Sets whether everyone can use the command by default.
+    ///
+    /// Commands with this set to `false` can't be used by anyone until they're allowed to with [`Handler::set_command_permissions`]
+    /// or [`HandlerBuilder::guild_command_with_permissions`]. Defaults to `true`, like Discord.
+    pub fn with_default_permission(mut self, new_default_permission: bool) -> Self {
+        match &mut self {
+            CommandDecl::Slash {
+                default_permission, ..
+            }
+            | CommandDecl::AsyncSlash {
+                default_permission, ..
+            }
+            | CommandDecl::Message {
+                default_permission, ..
+            }
+            | CommandDecl::User {
+                default_permission, ..
+            } => *default_permission = new_default_permission,
+        }
+        self
+    }
+
     fn description(&self, name: String) -> Command {
         Command {
             // These are only included on responses
             application_id: None,
             guild_id: None,
             id: None,
+            // Discord fills this in, and it's ignored when registering commands.
+            version: Id::new(1),
 
-            // TODO: support setting this
-            default_permission: None,
+            default_permission: match self {
+                CommandDecl::Slash {
+                    default_permission, ..
+                }
+                | CommandDecl::AsyncSlash {
+                    default_permission, ..
+                }
+                | CommandDecl::Message {
+                    default_permission, ..
+                }
+                | CommandDecl::User {
+                    default_permission, ..
+                } => Some(*default_permission),
+            },
 
             name,
 
-            description: if let CommandDecl::Slash { description, .. } = self {
-                *description
+            description: if let CommandDecl::Slash { description, .. }
+            | CommandDecl::AsyncSlash { description, .. } = self
+            {
+                description.to_string()
             } else {
-                ""
-            }
-            .to_string(),
+                String::new()
+            },
 
-            options: if let CommandDecl::Slash { options, .. } = self {
+            options: if let CommandDecl::Slash { options, .. }
+            | CommandDecl::AsyncSlash { options, .. } = self
+            {
                 options.clone()
             } else {
                 vec![]
             },
 
-            kind: match self {
-                CommandDecl::Slash { .. } => CommandType::ChatInput,
-                CommandDecl::Message { .. } => CommandType::Message,
-                CommandDecl::User { .. } => CommandType::User,
-            },
+            kind: self.kind(),
+        }
+    }
+
+    /// The type of command this declares.
+    pub(crate) fn kind(&self) -> CommandType {
+        match self {
+            CommandDecl::Slash { .. } | CommandDecl::AsyncSlash { .. } => CommandType::ChatInput,
+            CommandDecl::Message { .. } => CommandType::Message,
+            CommandDecl::User { .. } => CommandType::User,
         }
     }
 }
@@ -1,22 +1,41 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
 use std::pin::Pin;
+#[cfg(feature = "humantime")]
+use std::time::Duration;
 
-use twilight_model::application::callback::CallbackData;
-use twilight_model::application::callback::InteractionResponse;
+use thiserror::Error;
+use twilight_model::application::command;
 use twilight_model::application::command::BaseCommandOptionData;
+use twilight_model::application::command::ChannelCommandOptionData;
 use twilight_model::application::command::ChoiceCommandOptionData;
 use twilight_model::application::command::CommandOption;
 use twilight_model::application::command::CommandOptionChoice;
+use twilight_model::application::command::Number;
+use twilight_model::application::command::NumberCommandOptionData;
 use twilight_model::application::interaction::application_command::CommandDataOption;
 use twilight_model::application::interaction::application_command::CommandInteractionDataResolved;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
 use twilight_model::application::interaction::application_command::InteractionChannel;
+use twilight_model::application::interaction::application_command::InteractionMember;
+use twilight_model::channel::embed::Embed;
+use twilight_model::channel::message::MessageFlags;
 use twilight_model::guild::Role;
-use twilight_model::id::ChannelId;
-use twilight_model::id::RoleId;
-use twilight_model::id::UserId;
+use twilight_model::http::interaction::InteractionResponse;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::http::interaction::InteractionResponseType;
+use twilight_model::id::marker::UserMarker;
+use twilight_model::id::Id;
 use twilight_model::user::User;
 
+use crate::defer;
 use crate::DeferredFuture;
+use crate::WithAttachments;
 use crate::EMPTY_CALLBACK;
 
 /// Anything which can be mentioned; either a user or a role.
@@ -26,6 +45,47 @@ pub enum Mentionable {
     Role(Role),
 }
 
+impl Mentionable {
+    /// Formats a mention of this user or role, like `<@123>` or `<@&456>`.
+    ///
+    /// `twilight-mention`'s `Mention` trait can't be implemented for this, since it returns a different type for each variant.
+    pub fn mention(&self) -> String {
+        match self {
+            Mentionable::User(user) => format!("<@{}>", user.id),
+            Mentionable::Role(role) => format!("<@&{}>", role.id),
+        }
+    }
+}
+
+/// A user passed to a user option, or just their ID if Discord didn't send the rest of their details.
+///
+/// This is useful for commands like banning, where the user might not be in the guild (or might not even exist).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum UserOrId {
+    User(User),
+    Id(Id<UserMarker>),
+}
+
+impl UserOrId {
+    /// The ID of the user, whether or not their details were sent.
+    pub fn id(&self) -> Id<UserMarker> {
+        match self {
+            UserOrId::User(user) => user.id,
+            UserOrId::Id(id) => *id,
+        }
+    }
+}
+
+/// A user passed to a user option, along with their membership of the guild the command was used in.
+///
+/// Parsing this fails if the command wasn't used in a guild, or the user isn't a member of it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedMember {
+    pub user: User,
+    /// The guild-specific data for the user, like their nickname, roles and when they joined.
+    pub member: InteractionMember,
+}
+
 /// A trait to be implemented for C-like enums of choices for users to enter as arguments to your interaction.
 ///
 /// You should usually just implement this by deriving it.
@@ -43,18 +103,309 @@ pub enum Mentionable {
 ///     Qux,
 /// }
 ///
-/// assert_eq!(
-///     Foo::CHOICES,
-///     &[("Bar", 0), ("Baz", 1), ("not an ident!", 2)]
-/// );
+/// let names: Vec<_> = Foo::CHOICES.iter().map(|choice| choice.name).collect();
+/// assert_eq!(names, ["Bar", "Baz", "not an ident!"]);
+/// ```
+///
+/// By default, choices are integers, whose values are the enum's discriminants.
+/// Adding `#[choices(string)]` makes them strings instead, whose values are their names
+/// unless overridden with `#[value = "..."]`.
+///
+/// ```
+/// use twilight_interaction::Choices;
+///
+/// #[derive(Choices)]
+/// #[choices(string)]
+/// enum Language {
+///     #[name = "Rust"]
+///     #[value = "rs"]
+///     Rust,
+///     Python,
+/// }
+///
+/// let values: Vec<_> = Language::CHOICES.iter().map(|choice| choice.value).collect();
+/// assert_eq!(values, ["rs", "Python"]);
+/// ```
+///
+/// Similarly, `#[choices(number)]` makes them floating-point numbers, in which case every variant needs a `#[value = ...]`.
+/// Negative values have to be written as strings, like `#[value = "-1.5"]`.
+///
+/// ```
+/// use twilight_interaction::Choices;
+///
+/// #[derive(Choices)]
+/// #[choices(number)]
+/// enum AspectRatio {
+///     #[name = "16:9"]
+///     #[value = 1.7778]
+///     Widescreen,
+///     #[name = "4:3"]
+///     #[value = 1.3333]
+///     Standard,
+/// }
+///
+/// assert!(matches!(AspectRatio::from_value(1.3333), Some(AspectRatio::Standard)));
+/// ```
+///
+/// Like any other option type, wrapping a `Choices` enum in an `Option` makes the option optional.
+///
+/// ```
+/// use twilight_interaction::{Choices, SlashCommandOption};
+/// use twilight_model::application::command::CommandOption;
+///
+/// #[derive(Choices)]
+/// enum Size {
+///     Small,
+///     Large,
+/// }
+///
+/// let option = <Option<Size>>::describe("size".to_string(), "The size".to_string());
+/// match option {
+///     CommandOption::Integer(data) => assert!(!data.required),
+///     _ => unreachable!(),
+/// }
+/// ```
 pub trait Choices: Sized {
-    const CHOICES: &'static [(&'static str, i64)];
+    /// The type of the choices' values; either `i64`, `&'static str` or `f64`.
+    type Value: ChoiceValue;
+
+    const CHOICES: &'static [Choice<Self::Value>];
+
+    /// Gets the variant whose choice has the value `value`.
+    fn from_value(value: Self::Value) -> Option<Self>;
+}
+
+/// One of the choices of a [`Choices`] enum.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Choice<V = i64> {
+    /// The name shown to users.
+    pub name: &'static str,
+    /// The value Discord sends when this choice is picked.
+    pub value: V,
+}
+
+impl<V: ChoiceValue> Choice<V> {
+    /// Converts this into the form sent to Discord when registering a command.
+    fn to_command_option_choice(self) -> CommandOptionChoice {
+        self.value.into_choice(self.name.to_string())
+    }
+}
+
+/// A type which can be the value of a [`Choice`].
+///
+/// This is implemented for `i64`, `&'static str` and `f64`, for integer, string and number options respectively.
+pub trait ChoiceValue: Copy + 'static {
+    /// Describes an option of this type with the given choices.
+    #[doc(hidden)]
+    fn describe(
+        name: String,
+        description: String,
+        choices: Vec<CommandOptionChoice>,
+    ) -> CommandOption;
+
+    #[doc(hidden)]
+    fn into_choice(self, name: String) -> CommandOptionChoice;
+
+    /// Whether `data` is an option with this value.
+    #[doc(hidden)]
+    fn matches(self, data: &CommandDataOption) -> bool;
+
+    /// Formats the value of `data` as it would appear in Discord's JSON,
+    /// or returns `None` if it isn't the type of option this is the value of.
+    #[doc(hidden)]
+    fn format_value(data: &CommandDataOption) -> Option<String>;
+}
+
+impl ChoiceValue for i64 {
+    fn describe(
+        name: String,
+        description: String,
+        choices: Vec<CommandOptionChoice>,
+    ) -> CommandOption {
+        CommandOption::Integer(NumberCommandOptionData {
+            autocomplete: false,
+            choices,
+            description,
+            max_value: None,
+            min_value: None,
+            name,
+            required: true,
+        })
+    }
+
+    fn into_choice(self, name: String) -> CommandOptionChoice {
+        CommandOptionChoice::Int { name, value: self }
+    }
+
+    fn matches(self, data: &CommandDataOption) -> bool {
+        matches!(data.value, CommandOptionValue::Integer(value) if value == self)
+    }
+
+    fn format_value(data: &CommandDataOption) -> Option<String> {
+        match data.value {
+            CommandOptionValue::Integer(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl ChoiceValue for &'static str {
+    fn describe(
+        name: String,
+        description: String,
+        choices: Vec<CommandOptionChoice>,
+    ) -> CommandOption {
+        CommandOption::String(ChoiceCommandOptionData {
+            autocomplete: false,
+            choices,
+            description,
+            name,
+            required: true,
+        })
+    }
+
+    fn into_choice(self, name: String) -> CommandOptionChoice {
+        CommandOptionChoice::String {
+            name,
+            value: self.to_string(),
+        }
+    }
+
+    fn matches(self, data: &CommandDataOption) -> bool {
+        matches!(&data.value, CommandOptionValue::String(value) if value == self)
+    }
+
+    fn format_value(data: &CommandDataOption) -> Option<String> {
+        match &data.value {
+            CommandOptionValue::String(value) => Some(format!("{:?}", value)),
+            _ => None,
+        }
+    }
+}
+
+impl ChoiceValue for f64 {
+    fn describe(
+        name: String,
+        description: String,
+        choices: Vec<CommandOptionChoice>,
+    ) -> CommandOption {
+        CommandOption::Number(NumberCommandOptionData {
+            autocomplete: false,
+            choices,
+            description,
+            max_value: None,
+            min_value: None,
+            name,
+            required: true,
+        })
+    }
+
+    fn into_choice(self, name: String) -> CommandOptionChoice {
+        CommandOptionChoice::Number {
+            name,
+            value: Number(self),
+        }
+    }
+
+    fn matches(self, data: &CommandDataOption) -> bool {
+        // Discord sends back exactly the value it was given, so there's no need for any tolerance here.
+        // Like with plain `f64` options, whole numbers might be sent as integers.
+        match data.value {
+            CommandOptionValue::Number(value) => value.0 == self,
+            CommandOptionValue::Integer(value) => value as f64 == self,
+            _ => false,
+        }
+    }
+
+    fn format_value(data: &CommandDataOption) -> Option<String> {
+        match data.value {
+            CommandOptionValue::Number(value) => Some(value.0.to_string()),
+            CommandOptionValue::Integer(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Why an option sent by Discord couldn't be used.
+///
+/// This isn't `Copy`, since [`InvalidChoice`](Self::InvalidChoice) includes the value which was sent,
+/// and it's non-exhaustive so that reasons like that can be added without breaking code which matches on it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum OptionErrorReason {
+    /// A required option wasn't sent.
+    Missing,
+    /// The option was of the wrong type, or referred to something that wasn't in the resolved data.
+    WrongType,
+    /// The option was of the right type, but its value was outside the allowed range.
+    OutOfRange,
+    /// An option was sent which the command doesn't have.
+    Unknown,
+    /// The same option was sent more than once.
+    Duplicate,
+    /// The option was sent with a value which isn't one of its [`Choices`].
+    InvalidChoice {
+        /// The value which was sent, as it would appear in Discord's JSON.
+        value: String,
+    },
+}
+
+impl Display for OptionErrorReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionErrorReason::Missing => f.write_str("Missing option"),
+            OptionErrorReason::WrongType => f.write_str("Invalid option"),
+            OptionErrorReason::OutOfRange => f.write_str("Out of range value for option"),
+            OptionErrorReason::Unknown => f.write_str("Unknown option"),
+            OptionErrorReason::Duplicate => f.write_str("Duplicate option"),
+            OptionErrorReason::InvalidChoice { value } => {
+                write!(f, "Choice value {} not valid for option", value)
+            }
+        }
+    }
+}
+
+/// An error parsing one of the options sent with a slash command.
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+#[error("{reason} '{name}'")]
+pub struct OptionError {
+    /// The name of the offending option.
+    pub name: String,
+    pub reason: OptionErrorReason,
+}
+
+/// Every problem with the options sent with a slash command, so that they can all be reported at once.
+///
+/// This is never empty. A single [`OptionError`] can be converted into one with `?` or `.into()`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct OptionErrors(pub Vec<OptionError>);
+
+impl From<OptionError> for OptionErrors {
+    fn from(error: OptionError) -> Self {
+        Self(vec![error])
+    }
+}
 
-    fn from_discriminant(discriminant: i64) -> Option<Self>;
+impl Display for OptionErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for OptionErrors {}
+
 /// A type which can be used as an option for a slash command.
 pub trait SlashCommandOption: Sized {
+    /// Whether this is an `Option`, used to reject nested `Option`s.
+    #[doc(hidden)]
+    const OPTIONAL: bool = false;
+
     /// Generate a description for an option of this type with name `name` and description `description`.
     fn describe(name: String, description: String) -> CommandOption;
     /// Parse an instance of this type from an option given by Discord.
@@ -64,15 +415,32 @@ pub trait SlashCommandOption: Sized {
         data: Option<CommandDataOption>,
         resolved: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self>;
+
+    /// Like [`from_option`](Self::from_option), but returns why the option is invalid if it is.
+    ///
+    /// By default, this reports missing options as [`Missing`](OptionErrorReason::Missing)
+    /// and anything else as [`WrongType`](OptionErrorReason::WrongType).
+    fn parse_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Result<Self, OptionErrorReason> {
+        let reason = if data.is_some() {
+            OptionErrorReason::WrongType
+        } else {
+            OptionErrorReason::Missing
+        };
+        Self::from_option(data, resolved).ok_or(reason)
+    }
 }
 
 impl SlashCommandOption for String {
     fn describe(name: String, description: String) -> CommandOption {
         CommandOption::String(ChoiceCommandOptionData {
+            autocomplete: false,
             // TODO: make sure that this causes users to be able to enter anything, not nothing.
             choices: vec![],
-            name,
             description,
+            name,
             required: true,
         })
     }
@@ -81,8 +449,59 @@ impl SlashCommandOption for String {
         data: Option<CommandDataOption>,
         _: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::String { value, .. }) => Some(value),
+        match data?.value {
+            CommandOptionValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A single character, such as a card suit or a letter.
+///
+/// This version of Discord's API can't limit how long string options are,
+/// so this is registered as a plain string option and anything other than exactly one character is rejected as [`WrongType`](OptionErrorReason::WrongType).
+impl SlashCommandOption for char {
+    fn describe(name: String, description: String) -> CommandOption {
+        String::describe(name, description)
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        _: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        match data?.value {
+            CommandOptionValue::String(value) => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => Some(char),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A length of time written like `10m` or `1h 30m`, parsed with [`humantime`](https://docs.rs/humantime).
+///
+/// Discord doesn't have duration options, so this is registered as a plain string option
+/// and anything which can't be parsed is rejected as [`WrongType`](OptionErrorReason::WrongType).
+/// There's no upper limit, so commands should check against any of their own,
+/// like the 28 days members can be timed out for.
+///
+/// Requires the `humantime` feature to be enabled.
+#[cfg(feature = "humantime")]
+impl SlashCommandOption for Duration {
+    fn describe(name: String, description: String) -> CommandOption {
+        String::describe(name, description)
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        _: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        match data?.value {
+            CommandOptionValue::String(value) => humantime::parse_duration(value.trim()).ok(),
             _ => None,
         }
     }
@@ -90,10 +509,124 @@ impl SlashCommandOption for String {
 
 impl SlashCommandOption for i64 {
     fn describe(name: String, description: String) -> CommandOption {
-        CommandOption::Integer(ChoiceCommandOptionData {
+        CommandOption::Integer(NumberCommandOptionData {
+            autocomplete: false,
+            choices: vec![],
+            description,
+            max_value: None,
+            min_value: None,
+            name,
+            required: true,
+        })
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        _: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        match data?.value {
+            CommandOptionValue::Integer(value) => Some(value),
+            // Some clients send integer options as numbers, so accept them as long as they're whole numbers.
+            CommandOptionValue::Number(value) => {
+                let value = value.0;
+                if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 {
+                    Some(value as i64)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An integer between `MIN` and `MAX` inclusive, such as `IntRange<1, 100>` for a percentage.
+///
+/// Discord is told about the limits, so clients won't let users enter anything outside them,
+/// and anything which gets through anyway is rejected as [`OutOfRange`](OptionErrorReason::OutOfRange).
+///
+/// This dereferences to the `i64` it contains, so it can mostly be used like one.
+///
+/// Using this with `MIN` greater than `MAX` fails to compile, since no value would be allowed:
+///
+/// ```compile_fail
+/// use twilight_interaction::{slash_command, CommandDecl, IntRange};
+///
+/// #[slash_command(description("Picks a number", n = "The number"))]
+/// fn pick(n: IntRange<10, 1>) -> String {
+///     n.to_string()
+/// }
+/// # fn main() {
+/// let command: CommandDecl = pick::describe();
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IntRange<const MIN: i64, const MAX: i64>(pub i64);
+
+impl<const MIN: i64, const MAX: i64> IntRange<MIN, MAX> {
+    /// Fails to compile when referenced if the range is empty.
+    const NON_EMPTY: () = assert!(
+        MIN <= MAX,
+        "`IntRange`'s minimum is greater than its maximum"
+    );
+}
+
+impl<const MIN: i64, const MAX: i64> Deref for IntRange<MIN, MAX> {
+    type Target = i64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> SlashCommandOption for IntRange<MIN, MAX> {
+    fn describe(name: String, description: String) -> CommandOption {
+        let () = Self::NON_EMPTY;
+
+        CommandOption::Integer(NumberCommandOptionData {
+            autocomplete: false,
             choices: vec![],
+            description,
+            max_value: Some(command::CommandOptionValue::Integer(MAX)),
+            min_value: Some(command::CommandOptionValue::Integer(MIN)),
             name,
+            required: true,
+        })
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        Self::parse_option(data, resolved).ok()
+    }
+
+    /// Like the default implementation, but reports integers outside the range as [`OutOfRange`](OptionErrorReason::OutOfRange).
+    fn parse_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Result<Self, OptionErrorReason> {
+        let value = i64::parse_option(data, resolved)?;
+        if (MIN..=MAX).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(OptionErrorReason::OutOfRange)
+        }
+    }
+}
+
+/// The largest integer magnitude which an `f64` can represent exactly.
+const MAX_SAFE_INTEGER: i64 = 1 << 53;
+
+impl SlashCommandOption for f64 {
+    fn describe(name: String, description: String) -> CommandOption {
+        CommandOption::Number(NumberCommandOptionData {
+            autocomplete: false,
+            choices: vec![],
             description,
+            max_value: None,
+            min_value: None,
+            name,
             required: true,
         })
     }
@@ -102,8 +635,16 @@ impl SlashCommandOption for i64 {
         data: Option<CommandDataOption>,
         _: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::Integer { value, .. }) => Some(value),
+        match data?.value {
+            CommandOptionValue::Number(value) => Some(value.0),
+            // Likewise, some clients send number options as integers.
+            CommandOptionValue::Integer(value) => {
+                if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&value) {
+                    Some(value as f64)
+                } else {
+                    None
+                }
+            }
             _ => None,
         }
     }
@@ -112,8 +653,8 @@ impl SlashCommandOption for i64 {
 impl SlashCommandOption for bool {
     fn describe(name: String, description: String) -> CommandOption {
         CommandOption::Boolean(BaseCommandOptionData {
-            name,
             description,
+            name,
             required: true,
         })
     }
@@ -122,8 +663,8 @@ impl SlashCommandOption for bool {
         data: Option<CommandDataOption>,
         _: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::Boolean { value, .. }) => Some(value),
+        match data?.value {
+            CommandOptionValue::Boolean(value) => Some(value),
             _ => None,
         }
     }
@@ -132,8 +673,8 @@ impl SlashCommandOption for bool {
 impl SlashCommandOption for User {
     fn describe(name: String, description: String) -> CommandOption {
         CommandOption::User(BaseCommandOptionData {
-            name,
             description,
+            name,
             required: true,
         })
     }
@@ -142,28 +683,75 @@ impl SlashCommandOption for User {
         data: Option<CommandDataOption>,
         resolved: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::String { value, .. }) => {
-                let user_id = UserId::from(value.parse::<u64>().ok()?);
-
-                resolved.and_then(|resolved| {
-                    resolved
-                        .users
-                        .iter()
-                        .find(|user| user.id == user_id)
-                        .cloned()
-                })
+        match data?.value {
+            CommandOptionValue::User(user_id) => resolved?.users.get(&user_id).cloned(),
+            _ => None,
+        }
+    }
+}
+
+impl SlashCommandOption for UserOrId {
+    fn describe(name: String, description: String) -> CommandOption {
+        User::describe(name, description)
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        match data?.value {
+            CommandOptionValue::User(user_id) => {
+                let user = resolved.and_then(|resolved| resolved.users.get(&user_id).cloned());
+                Some(user.map_or(UserOrId::Id(user_id), UserOrId::User))
             }
             _ => None,
         }
     }
 }
 
+impl SlashCommandOption for ResolvedMember {
+    fn describe(name: String, description: String) -> CommandOption {
+        User::describe(name, description)
+    }
+
+    fn from_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Option<Self> {
+        let user = User::from_option(data, resolved)?;
+        let member = resolved?.members.get(&user.id).cloned()?;
+
+        Some(Self { user, member })
+    }
+}
+
+/// Channels are taken from the resolved data Discord sends with the interaction, and returned in full.
+///
+/// Alongside the channel's ID, name and type, this includes its `permissions` field,
+/// which holds the permissions the user who invoked the command has in that channel, with overwrites applied.
+///
+/// # Examples
+/// ```no_run
+/// use twilight_interaction::{slash_command, Context};
+/// use twilight_model::application::interaction::application_command::InteractionChannel;
+/// use twilight_model::guild::Permissions;
+///
+/// #[slash_command(description("Clears a channel", channel = "The channel to clear"))]
+/// fn clear(_: Context, channel: InteractionChannel) -> String {
+///     if channel.permissions.contains(Permissions::MANAGE_MESSAGES) {
+///         format!("Clearing {}...", channel.name)
+///     } else {
+///         format!("You can't manage messages in {}", channel.name)
+///     }
+/// }
+/// # fn main() {}
+/// ```
 impl SlashCommandOption for InteractionChannel {
     fn describe(name: String, description: String) -> CommandOption {
-        CommandOption::Channel(BaseCommandOptionData {
-            name,
+        CommandOption::Channel(ChannelCommandOptionData {
+            channel_types: vec![],
             description,
+            name,
             required: true,
         })
     }
@@ -172,18 +760,8 @@ impl SlashCommandOption for InteractionChannel {
         data: Option<CommandDataOption>,
         resolved: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::String { value, .. }) => {
-                let channel_id = ChannelId::from(value.parse::<u64>().ok()?);
-
-                resolved.and_then(|resolved| {
-                    resolved
-                        .channels
-                        .iter()
-                        .find(|channel| channel.id == channel_id)
-                        .cloned()
-                })
-            }
+        match data?.value {
+            CommandOptionValue::Channel(channel_id) => resolved?.channels.get(&channel_id).cloned(),
             _ => None,
         }
     }
@@ -192,8 +770,8 @@ impl SlashCommandOption for InteractionChannel {
 impl SlashCommandOption for Role {
     fn describe(name: String, description: String) -> CommandOption {
         CommandOption::Role(BaseCommandOptionData {
-            name,
             description,
+            name,
             required: true,
         })
     }
@@ -202,18 +780,8 @@ impl SlashCommandOption for Role {
         data: Option<CommandDataOption>,
         resolved: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::String { value, .. }) => {
-                let role_id = RoleId::from(value.parse::<u64>().ok()?);
-
-                resolved.and_then(|resolved| {
-                    resolved
-                        .roles
-                        .iter()
-                        .find(|role| role.id == role_id)
-                        .cloned()
-                })
-            }
+        match data?.value {
+            CommandOptionValue::Role(role_id) => resolved?.roles.get(&role_id).cloned(),
             _ => None,
         }
     }
@@ -222,8 +790,8 @@ impl SlashCommandOption for Role {
 impl SlashCommandOption for Mentionable {
     fn describe(name: String, description: String) -> CommandOption {
         CommandOption::Mentionable(BaseCommandOptionData {
-            name,
             description,
+            name,
             required: true,
         })
     }
@@ -232,27 +800,22 @@ impl SlashCommandOption for Mentionable {
         data: Option<CommandDataOption>,
         resolved: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::String { value, .. }) => {
-                let id = value.parse::<u64>().ok()?;
-
-                resolved.and_then(|resolved| {
-                    // First try to find a user matching the ID, otherwise look for a role.
-                    resolved
-                        .users
-                        .iter()
-                        .find(|user| user.id == UserId::from(id))
-                        .cloned()
-                        .map(Mentionable::User)
-                        .or_else(|| {
-                            resolved
-                                .roles
-                                .iter()
-                                .find(|role| role.id == RoleId::from(id))
-                                .cloned()
-                                .map(Mentionable::Role)
-                        })
-                })
+        match data?.value {
+            CommandOptionValue::Mentionable(id) => {
+                let resolved = resolved?;
+                // First try to find a user matching the ID, otherwise look for a role.
+                resolved
+                    .users
+                    .get(&id.cast())
+                    .cloned()
+                    .map(Mentionable::User)
+                    .or_else(|| {
+                        resolved
+                            .roles
+                            .get(&id.cast())
+                            .cloned()
+                            .map(Mentionable::Role)
+                    })
             }
             _ => None,
         }
@@ -261,46 +824,75 @@ impl SlashCommandOption for Mentionable {
 
 impl<T: Choices> SlashCommandOption for T {
     fn describe(name: String, description: String) -> CommandOption {
-        CommandOption::Integer(ChoiceCommandOptionData {
-            choices: Self::CHOICES
-                .iter()
-                .map(|&(name, value)| CommandOptionChoice::Int {
-                    name: name.to_string(),
-                    value,
-                })
-                .collect(),
-            name,
-            description,
-            required: true,
-        })
+        let choices = Self::CHOICES
+            .iter()
+            .map(|choice| choice.to_command_option_choice())
+            .collect();
+        T::Value::describe(name, description, choices)
     }
 
     fn from_option(
         data: Option<CommandDataOption>,
         _: Option<&CommandInteractionDataResolved>,
     ) -> Option<Self> {
-        match data {
-            Some(CommandDataOption::Integer { value, .. }) => Self::from_discriminant(value),
-            _ => None,
-        }
+        let data = data?;
+        let choice = Self::CHOICES
+            .iter()
+            .find(|choice| choice.value.matches(&data))?;
+        Self::from_value(choice.value)
+    }
+
+    /// Like the default implementation, but reports values which aren't one of the choices
+    /// as [`InvalidChoice`](OptionErrorReason::InvalidChoice), along with the value that was sent.
+    /// Values of the wrong type, like a string for integer choices, are still [`WrongType`](OptionErrorReason::WrongType).
+    fn parse_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Result<Self, OptionErrorReason> {
+        let data = data.ok_or(OptionErrorReason::Missing)?;
+        let value = T::Value::format_value(&data).ok_or(OptionErrorReason::WrongType)?;
+        Self::from_option(Some(data), resolved).ok_or(OptionErrorReason::InvalidChoice { value })
     }
 }
 
-// FIXME: somehow disallow `Option<Option<Option<T>>>`.
+/// Fails to compile when referenced if `T` is itself an `Option`.
+struct NotNested<T>(PhantomData<T>);
+
+impl<T: SlashCommandOption> NotNested<T> {
+    const NOT_NESTED: () = assert!(
+        !T::OPTIONAL,
+        "Nested `Option`s can't be told apart from a plain `Option`"
+    );
+}
+
+/// An optional option, which is `None` when the user leaves it out.
+///
+/// Nested `Option`s like `Option<Option<T>>` aren't allowed, since an option can only be left out one way;
+/// the inner `Option` would always be `Some`. There's no way to stop them implementing this trait,
+/// so [`slash_command`](crate::slash_command) rejects them when it can see them,
+/// and describing one fails to compile otherwise (for example, when hidden behind a type alias):
+///
+/// ```compile_fail
+/// use twilight_interaction::{slash_command, CommandDecl};
+///
+/// type MaybeInt = Option<i64>;
+///
+/// #[slash_command(description("Picks a number", n = "The number"))]
+/// fn pick(n: Option<MaybeInt>) -> String {
+///     format!("{:?}", n)
+/// }
+/// # fn main() {
+/// let command: CommandDecl = pick::describe();
+/// # }
+/// ```
 impl<T: SlashCommandOption> SlashCommandOption for Option<T> {
+    const OPTIONAL: bool = true;
+
     fn describe(name: String, description: String) -> CommandOption {
+        let () = NotNested::<T>::NOT_NESTED;
+
         let mut option = T::describe(name, description);
-        match &mut option {
-            CommandOption::SubCommand(data) | CommandOption::SubCommandGroup(data) => {
-                data.required = false
-            }
-            CommandOption::String(data) | CommandOption::Integer(data) => data.required = false,
-            CommandOption::Boolean(data)
-            | CommandOption::User(data)
-            | CommandOption::Channel(data)
-            | CommandOption::Role(data)
-            | CommandOption::Mentionable(data) => data.required = false,
-        }
+        set_required(&mut option, false);
         option
     }
 
@@ -313,30 +905,238 @@ impl<T: SlashCommandOption> SlashCommandOption for Option<T> {
             None => Some(None),
         }
     }
+
+    fn parse_option(
+        data: Option<CommandDataOption>,
+        resolved: Option<&CommandInteractionDataResolved>,
+    ) -> Result<Self, OptionErrorReason> {
+        match data {
+            Some(data) => T::parse_option(Some(data), resolved).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Makes `response` ephemeral, unless it's an immediate response which has already set its own flags.
+///
+/// Used by [`slash_command`](crate::slash_command)'s `ephemeral` argument.
+#[doc(hidden)]
+pub fn ephemeral_by_default(
+    (mut response, future): (InteractionResponse, Option<DeferredFuture>),
+) -> (InteractionResponse, Option<DeferredFuture>) {
+    match (response.kind, &mut response.data) {
+        (InteractionResponseType::ChannelMessageWithSource, Some(data)) if data.flags.is_none() => {
+            data.flags = Some(MessageFlags::EPHEMERAL)
+        }
+        // The loading message decides whether the eventual response is ephemeral,
+        // so the deferred future's output can't override this.
+        (InteractionResponseType::DeferredChannelMessageWithSource, data) => {
+            let data = data.get_or_insert(EMPTY_CALLBACK);
+            data.flags =
+                Some(data.flags.unwrap_or_else(MessageFlags::empty) | MessageFlags::EPHEMERAL)
+        }
+        _ => {}
+    }
+    (response, future)
+}
+
+/// Replaces `response`'s loading state with a message saying `content`, if it's deferred.
+///
+/// Discord doesn't show any content while a response is deferred, so this responds immediately instead,
+/// and the deferred future's output replaces the message the same way it would have replaced the loading state.
+///
+/// Used by [`slash_command`](crate::slash_command)'s `loading` argument.
+#[doc(hidden)]
+pub fn with_loading_message(
+    (response, future): (InteractionResponse, Option<DeferredFuture>),
+    content: &str,
+) -> (InteractionResponse, Option<DeferredFuture>) {
+    match (response, future) {
+        (
+            InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data,
+            },
+            Some(future),
+        ) => {
+            let response = InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(InteractionResponseData {
+                    content: Some(content.to_string()),
+                    flags: data.and_then(|data| data.flags),
+                    ..EMPTY_CALLBACK
+                }),
+            };
+            // Edits leave out any fields which aren't set, so the loading message has to be cleared explicitly.
+            let future: DeferredFuture = Box::pin(async move {
+                let mut output = future.await;
+                output.data.content.get_or_insert_with(String::new);
+                output
+            });
+            (response, Some(future))
+        }
+        other => other,
+    }
+}
+
+/// Sets whether Discord requires `option` to be given.
+///
+/// Used by [`slash_command`](crate::slash_command)'s `required` argument.
+#[doc(hidden)]
+pub fn set_required(option: &mut CommandOption, required: bool) {
+    // This deliberately doesn't have a catch-all arm, so that any new kinds of option
+    // have to be added here rather than silently staying required.
+    match option {
+        // Subcommands can't be required, since the user picks one of them.
+        CommandOption::SubCommand(_) | CommandOption::SubCommandGroup(_) => {}
+        CommandOption::String(data) => data.required = required,
+        CommandOption::Integer(data) | CommandOption::Number(data) => data.required = required,
+        CommandOption::Channel(data) => data.required = required,
+        CommandOption::Boolean(data)
+        | CommandOption::User(data)
+        | CommandOption::Role(data)
+        | CommandOption::Mentionable(data)
+        | CommandOption::Attachment(data) => data.required = required,
+    }
+}
+
+/// Makes Discord ask the handler's [autocomplete](crate::HandlerBuilder::autocomplete) handler for suggestions
+/// while the user is filling in `option`.
+///
+/// Used by [`slash_command`](crate::slash_command)'s `autocomplete` argument.
+///
+/// # Panics
+/// Panics if `option` has fixed choices, since Discord doesn't allow both,
+/// or if it isn't a string, integer or number option.
+#[doc(hidden)]
+pub fn set_autocomplete(option: &mut CommandOption) {
+    let (name, choices, autocomplete) = match option {
+        CommandOption::String(data) => (&data.name, &data.choices, &mut data.autocomplete),
+        CommandOption::Integer(data) | CommandOption::Number(data) => {
+            (&data.name, &data.choices, &mut data.autocomplete)
+        }
+        _ => panic!("Only string, integer and number options can use autocomplete"),
+    };
+    if !choices.is_empty() {
+        panic!(
+            "Option '{}' has fixed choices, so it can't use autocomplete",
+            name
+        );
+    }
+    *autocomplete = true;
+}
+
+/// Whether Discord requires `option` to be given.
+fn is_required(option: &CommandOption) -> bool {
+    match option {
+        CommandOption::SubCommand(_) | CommandOption::SubCommandGroup(_) => false,
+        CommandOption::String(data) => data.required,
+        CommandOption::Integer(data) | CommandOption::Number(data) => data.required,
+        CommandOption::Channel(data) => data.required,
+        CommandOption::Boolean(data)
+        | CommandOption::User(data)
+        | CommandOption::Role(data)
+        | CommandOption::Mentionable(data)
+        | CommandOption::Attachment(data) => data.required,
+    }
+}
+
+/// Moves all the required options before the optional ones, since Discord rejects commands where they're mixed up.
+///
+/// Otherwise, the options stay in the same order.
+#[doc(hidden)]
+pub fn sort_options(options: &mut [CommandOption]) {
+    // `sort_by_key` is stable, and `false` sorts before `true`.
+    options.sort_by_key(|option| !is_required(option));
+}
+
+/// Replaces any subcommand wrappers in `options` with the options inside them.
+///
+/// Commands declared with [`slash_command`](crate::slash_command) don't have subcommands,
+/// but if one is turned into a subcommand (or group) on Discord's end,
+/// this lets its options still be found instead of the wrapper being rejected as an unknown option.
+#[doc(hidden)]
+pub fn flatten_options(options: Vec<CommandDataOption>) -> Vec<CommandDataOption> {
+    let mut flattened = Vec::with_capacity(options.len());
+    for option in options {
+        match option.value {
+            CommandOptionValue::SubCommand(options)
+            | CommandOptionValue::SubCommandGroup(options) => {
+                flattened.extend(flatten_options(options))
+            }
+            _ => flattened.push(option),
+        }
+    }
+    flattened
 }
 
 /// A type which can be used as a response from a slash command.
 pub trait IntoCallbackData {
-    fn into_callback_data(self) -> CallbackData;
+    fn into_callback_data(self) -> InteractionResponseData;
 }
 
-impl IntoCallbackData for CallbackData {
-    fn into_callback_data(self) -> CallbackData {
+impl IntoCallbackData for InteractionResponseData {
+    fn into_callback_data(self) -> InteractionResponseData {
         self
     }
 }
 
-// TODO: Ideally this'd be implemented for anything which implements `ToString`,
-// but I can't because `CallbackData` might implement it in the future.
+// TODO: Ideally this'd be implemented for anything which implements `ToString` (or `Into<String>`),
+// but I can't because `InteractionResponseData` might implement it in the future.
+// So instead, the common string types are implemented individually.
 impl IntoCallbackData for String {
-    fn into_callback_data(self) -> CallbackData {
-        CallbackData {
+    fn into_callback_data(self) -> InteractionResponseData {
+        InteractionResponseData {
             content: Some(self),
-            allowed_mentions: None,
-            embeds: vec![],
-            flags: None,
-            tts: None,
-            components: None,
+            ..EMPTY_CALLBACK
+        }
+    }
+}
+
+impl IntoCallbackData for &str {
+    fn into_callback_data(self) -> InteractionResponseData {
+        self.to_string().into_callback_data()
+    }
+}
+
+impl IntoCallbackData for Cow<'_, str> {
+    fn into_callback_data(self) -> InteractionResponseData {
+        self.into_owned().into_callback_data()
+    }
+}
+
+impl IntoCallbackData for Embed {
+    fn into_callback_data(self) -> InteractionResponseData {
+        vec![self].into_callback_data()
+    }
+}
+
+impl IntoCallbackData for Vec<Embed> {
+    fn into_callback_data(self) -> InteractionResponseData {
+        InteractionResponseData {
+            embeds: Some(self),
+            ..EMPTY_CALLBACK
+        }
+    }
+}
+
+/// Responds with `Err`'s error message as an ephemeral message, so that commands can use `?`.
+///
+/// Note that a deferred response's visibility is decided when it's deferred,
+/// so an error returned from an `async` command is only ephemeral if the command is.
+impl<T, E> IntoCallbackData for Result<T, E>
+where
+    T: IntoCallbackData,
+    E: Display,
+{
+    fn into_callback_data(self) -> InteractionResponseData {
+        match self {
+            Ok(data) => data.into_callback_data(),
+            Err(err) => InteractionResponseData {
+                content: Some(err.to_string()),
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..EMPTY_CALLBACK
+            },
         }
     }
 }
@@ -351,20 +1151,133 @@ where
 {
     fn into_interaction_response(self) -> (InteractionResponse, Option<DeferredFuture>) {
         (
-            InteractionResponse::ChannelMessageWithSource(self.into_callback_data()),
+            InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(self.into_callback_data()),
+            },
             None,
         )
     }
 }
 
+impl CommandResponse for WithAttachments {
+    fn into_interaction_response(self) -> (InteractionResponse, Option<DeferredFuture>) {
+        self.into_interaction_response()
+    }
+}
+
 impl<T> CommandResponse for Pin<Box<dyn Future<Output = T> + Send>>
 where
-    T: IntoCallbackData + 'static,
+    WithAttachments: From<T>,
+    T: 'static,
 {
     fn into_interaction_response(self) -> (InteractionResponse, Option<DeferredFuture>) {
         (
-            InteractionResponse::DeferredChannelMessageWithSource(EMPTY_CALLBACK),
-            Some(Box::pin(async move { self.await.into_callback_data() })),
+            InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data: None,
+            },
+            Some(defer(self)),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::application::command::Number;
+    use twilight_model::application::interaction::application_command::CommandDataOption;
+    use twilight_model::application::interaction::application_command::CommandOptionValue;
+
+    use super::Choice;
+    use super::Choices;
+    use super::OptionErrorReason;
+    use super::SlashCommandOption;
+
+    #[derive(Debug, PartialEq)]
+    enum Size {
+        Small,
+        Large,
+    }
+
+    impl Choices for Size {
+        type Value = i64;
+
+        const CHOICES: &'static [Choice<i64>] = &[
+            Choice {
+                name: "Small",
+                value: 0,
+            },
+            Choice {
+                name: "Large",
+                value: 1,
+            },
+        ];
+
+        fn from_value(value: i64) -> Option<Self> {
+            match value {
+                0 => Some(Size::Small),
+                1 => Some(Size::Large),
+                _ => None,
+            }
+        }
+    }
+
+    fn option(value: CommandOptionValue) -> Option<CommandDataOption> {
+        Some(CommandDataOption {
+            focused: false,
+            name: "n".to_string(),
+            value,
+        })
+    }
+
+    #[test]
+    fn integer_from_number() {
+        let parse =
+            |value| i64::from_option(option(CommandOptionValue::Number(Number(value))), None);
+        assert_eq!(parse(2.0), Some(2));
+        assert_eq!(parse(-7.0), Some(-7));
+        assert_eq!(parse(2.5), None);
+        assert_eq!(parse(f64::NAN), None);
+        assert_eq!(parse(1e20), None);
+    }
+
+    #[test]
+    fn number_from_integer() {
+        let parse = |value| f64::from_option(option(CommandOptionValue::Integer(value)), None);
+        assert_eq!(parse(3), Some(3.0));
+        assert_eq!(parse(-(1 << 53)), Some(-9007199254740992.0));
+        assert_eq!(parse((1 << 53) + 1), None);
+    }
+
+    #[test]
+    fn wrong_type() {
+        assert_eq!(
+            i64::from_option(option(CommandOptionValue::String("2".to_string())), None),
+            None
+        );
+        assert_eq!(
+            f64::from_option(option(CommandOptionValue::Boolean(true)), None),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_choice() {
+        let parse = |value| Size::parse_option(option(value), None);
+        assert_eq!(parse(CommandOptionValue::Integer(1)), Ok(Size::Large));
+        assert_eq!(
+            parse(CommandOptionValue::Integer(2)),
+            Err(OptionErrorReason::InvalidChoice {
+                value: "2".to_string()
+            })
+        );
+        assert_eq!(
+            parse(CommandOptionValue::String("1".to_string())),
+            Err(OptionErrorReason::WrongType)
+        );
+        assert_eq!(
+            Size::parse_option(None, None),
+            Err(OptionErrorReason::Missing)
+        );
+    }
+}
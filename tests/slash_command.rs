@@ -0,0 +1,318 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use twilight_interaction::slash_command;
+use twilight_interaction::CommandDecl;
+use twilight_interaction::CommandIds;
+use twilight_interaction::CommandScope;
+use twilight_interaction::HandleError;
+use twilight_interaction::Handler;
+use twilight_interaction::OptionError;
+use twilight_interaction::OptionErrorReason;
+use twilight_interaction::OptionErrors;
+use twilight_model::application::command::CommandOption;
+use twilight_model::application::command::CommandType;
+use twilight_model::application::interaction::application_command::ApplicationCommand;
+use twilight_model::application::interaction::application_command::CommandData;
+use twilight_model::application::interaction::application_command::CommandDataOption;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::application::interaction::Interaction;
+use twilight_model::application::interaction::InteractionType;
+use twilight_model::id::Id;
+
+/// An interaction using the global slash command `name`, with `options`.
+fn interaction(ids: &CommandIds, name: &str, options: Vec<CommandDataOption>) -> Interaction {
+    Interaction::ApplicationCommand(Box::new(ApplicationCommand {
+        application_id: Id::new(1),
+        channel_id: Id::new(1),
+        data: CommandData {
+            id: ids
+                .get(CommandScope::Global, CommandType::ChatInput, name)
+                .unwrap(),
+            name: name.to_string(),
+            kind: CommandType::ChatInput,
+            options,
+            resolved: None,
+            target_id: None,
+        },
+        guild_id: None,
+        guild_locale: None,
+        id: Id::new(1),
+        kind: InteractionType::ApplicationCommand,
+        locale: "en-US".to_string(),
+        member: None,
+        token: "token".to_string(),
+        user: None,
+    }))
+}
+
+fn option(name: &str, value: CommandOptionValue) -> CommandDataOption {
+    CommandDataOption {
+        focused: false,
+        name: name.to_string(),
+        value,
+    }
+}
+
+/// Runs `command`, registered globally as `name`, with `options`.
+///
+/// Returns the content of the message it responds with, or the error it fails with.
+fn run(
+    name: &'static str,
+    command: CommandDecl,
+    options: Vec<CommandDataOption>,
+) -> Result<String, HandleError> {
+    let error = Arc::new(Mutex::new(None));
+    let (handler, ids) = Handler::builder_without_client(())
+        .global_command(name, command)
+        .on_error({
+            let error = Arc::clone(&error);
+            move |err: &HandleError| {
+                *error.lock().unwrap() = Some(err.clone());
+                "error"
+            }
+        })
+        .build_offline();
+
+    let content = handler
+        .handle(interaction(&ids, name, options))
+        .into_response()
+        .data
+        .and_then(|data| data.content)
+        .unwrap();
+    let error = error.lock().unwrap().take();
+    match error {
+        Some(err) => Err(err),
+        None => Ok(content),
+    }
+}
+
+/// The names of the options `command` is registered with, along with whether they're required.
+fn describe(command: CommandDecl) -> Vec<(String, bool)> {
+    let options = match command {
+        CommandDecl::Slash { options, .. } => options,
+        _ => unreachable!(),
+    };
+    options
+        .into_iter()
+        .map(|option| match option {
+            CommandOption::String(data) => (data.name, data.required),
+            CommandOption::Integer(data) => (data.name, data.required),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn invalid_option(command: &str, errors: Vec<(&str, OptionErrorReason)>) -> HandleError {
+    HandleError::InvalidOption {
+        command: command.to_string(),
+        errors: OptionErrors(
+            errors
+                .into_iter()
+                .map(|(name, reason)| OptionError {
+                    name: name.to_string(),
+                    reason,
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[slash_command(
+    description("Repeats a word", word = "The word", times = "How many times"),
+    default(times = "2")
+)]
+fn repeat(word: String, times: Option<i64>) -> String {
+    format!("{:?} {:?}", word, times)
+}
+
+#[test]
+fn optional_default() {
+    let word = || option("word", CommandOptionValue::String("hi".to_string()));
+    assert_eq!(
+        run(repeat::NAME, repeat::describe(), vec![word()]),
+        Ok(r#""hi" Some(2)"#.to_string())
+    );
+    assert_eq!(
+        run(
+            repeat::NAME,
+            repeat::describe(),
+            vec![word(), option("times", CommandOptionValue::Integer(3))]
+        ),
+        Ok(r#""hi" Some(3)"#.to_string())
+    );
+}
+
+#[slash_command(description(
+    "Adds two numbers",
+    first = "The first number",
+    second = "The second number"
+))]
+fn add(first: i64, second: i64) -> String {
+    (first + second).to_string()
+}
+
+#[test]
+fn every_invalid_option() {
+    let options = vec![
+        option("first", CommandOptionValue::String("one".to_string())),
+        option("second", CommandOptionValue::String("two".to_string())),
+        option("first", CommandOptionValue::Integer(1)),
+    ];
+    assert_eq!(
+        run(add::NAME, add::describe(), options),
+        Err(invalid_option(
+            "add",
+            vec![
+                ("first", OptionErrorReason::Duplicate),
+                ("first", OptionErrorReason::WrongType),
+                ("second", OptionErrorReason::WrongType),
+            ]
+        ))
+    );
+}
+
+#[test]
+fn flattened_subcommands() {
+    let options = vec![
+        option("first", CommandOptionValue::Integer(1)),
+        option("second", CommandOptionValue::Integer(2)),
+    ];
+    let subcommand = vec![option("sum", CommandOptionValue::SubCommand(options))];
+    let group = vec![option(
+        "maths",
+        CommandOptionValue::SubCommandGroup(subcommand.clone()),
+    )];
+
+    assert_eq!(
+        run(add::NAME, add::describe(), subcommand),
+        Ok("3".to_string())
+    );
+    assert_eq!(run(add::NAME, add::describe(), group), Ok("3".to_string()));
+}
+
+#[slash_command(description("Tags something", tag = "The tag", count = "How many to tag"))]
+fn tag(tag: Option<String>, count: i64) -> String {
+    format!("{:?} {}", tag, count)
+}
+
+#[test]
+fn required_options_first() {
+    assert_eq!(
+        describe(tag::describe()),
+        [("count".to_string(), true), ("tag".to_string(), false)]
+    );
+}
+
+#[slash_command(
+    description("Labels something", label = "The label", count = "How many to label"),
+    required(label = true, count = false)
+)]
+fn label(label: Option<String>, count: i64) -> String {
+    format!("{:?} {}", label, count)
+}
+
+#[test]
+fn required_overrides() {
+    assert_eq!(
+        describe(label::describe()),
+        [("label".to_string(), true), ("count".to_string(), false)]
+    );
+
+    // Overriding requiredness only changes what Discord is told, not how the arguments are parsed.
+    assert_eq!(
+        run(label::NAME, label::describe(), vec![]),
+        Err(invalid_option(
+            "label",
+            vec![("count", OptionErrorReason::Missing)]
+        ))
+    );
+    assert_eq!(
+        run(
+            label::NAME,
+            label::describe(),
+            vec![option("count", CommandOptionValue::Integer(2))]
+        ),
+        Ok("None 2".to_string())
+    );
+}
+
+#[slash_command(description("Picks colours", colours = "A colour"))]
+fn pick(colours: [Option<String>; 3]) -> String {
+    format!("{:?}", colours)
+}
+
+#[slash_command(description("Picks colours", colours = "A colour"), kebab_case = false)]
+fn pick_snake(colours: [Option<String>; 2]) -> String {
+    format!("{:?}", colours)
+}
+
+#[test]
+fn array_options() {
+    assert_eq!(
+        describe(pick::describe()),
+        [
+            ("colours-1".to_string(), false),
+            ("colours-2".to_string(), false),
+            ("colours-3".to_string(), false),
+        ]
+    );
+    assert_eq!(
+        run(
+            pick::NAME,
+            pick::describe(),
+            vec![option(
+                "colours-2",
+                CommandOptionValue::String("red".to_string())
+            )]
+        ),
+        Ok(r#"[None, Some("red"), None]"#.to_string())
+    );
+}
+
+#[test]
+fn snake_case_array_options() {
+    assert_eq!(pick_snake::NAME, "pick_snake");
+    assert_eq!(
+        describe(pick_snake::describe()),
+        [
+            ("colours_1".to_string(), false),
+            ("colours_2".to_string(), false),
+        ]
+    );
+    assert_eq!(
+        run(
+            pick_snake::NAME,
+            pick_snake::describe(),
+            vec![option(
+                "colours_1",
+                CommandOptionValue::String("red".to_string())
+            )]
+        ),
+        Ok(r#"[Some("red"), None]"#.to_string())
+    );
+}
+
+#[slash_command(
+    description("Looks up a user", id = "The user's ID"),
+    rename(id = "user_id-2")
+)]
+fn look_up(id: i64) -> String {
+    id.to_string()
+}
+
+#[test]
+fn renamed_option_characters() {
+    assert_eq!(
+        describe(look_up::describe()),
+        [("user_id-2".to_string(), true)]
+    );
+    assert_eq!(
+        run(
+            look_up::NAME,
+            look_up::describe(),
+            vec![option("user_id-2", CommandOptionValue::Integer(5))]
+        ),
+        Ok("5".to_string())
+    );
+}